@@ -0,0 +1,235 @@
+use super::{AttributeData, Mesh};
+use cgmath::{InnerSpace, Vector3};
+use std::f32::consts::PI;
+
+/// The name of the custom attribute in which [`Mesh::bake_ambient_occlusion`]
+/// stores its results.
+pub const AMBIENT_OCCLUSION_ATTRIBUTE: &str = "ambient_occlusion";
+
+/// Settings for [`Mesh::ambient_occlusion`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmbientOcclusionSettings {
+    /// The number of rays to cast from each vertex. More rays give smoother
+    /// results but take proportionally longer.
+    pub sample_count: usize,
+
+    /// Geometry that is farther than this from a vertex does not occlude it.
+    /// Smaller distances limit the darkening to crevices and corners.
+    pub max_distance: f32,
+
+    /// Seeds the random sample directions. The same seed always gives the
+    /// same results for the same mesh.
+    pub seed: u64,
+}
+
+impl Default for AmbientOcclusionSettings {
+    fn default() -> Self {
+        AmbientOcclusionSettings {
+            sample_count: 64,
+            max_distance: f32::INFINITY,
+            seed: 0,
+        }
+    }
+}
+
+impl Mesh {
+    /// Computes the ambient occlusion at each vertex and stores it as a float
+    /// attribute named [`AMBIENT_OCCLUSION_ATTRIBUTE`], replacing any previous
+    /// values. See [`Mesh::ambient_occlusion`].
+    pub fn bake_ambient_occlusion(&mut self, settings: &AmbientOcclusionSettings) {
+        let values = self.ambient_occlusion(settings);
+        self.attributes.insert(
+            AMBIENT_OCCLUSION_ATTRIBUTE.to_string(),
+            AttributeData::Float(values),
+        );
+    }
+
+    /// Computes the ambient occlusion at each vertex, from `0` where the vertex
+    /// is completely enclosed to `1` where nothing blocks it.
+    ///
+    /// Rays are cast from each vertex in random directions over the hemisphere
+    /// around its normal, with more rays near the normal in proportion to the
+    /// cosine of their angle to it. The result is the fraction of rays that
+    /// escape without hitting the mesh within the maximum distance. Each
+    /// vertex's rays depend only on the seed and the vertex's index, so the
+    /// results are deterministic. Vertices with zero normals get `1`.
+    pub fn ambient_occlusion(&self, settings: &AmbientOcclusionSettings) -> Vec<f32> {
+        let (min, max) = match self.bounding_box() {
+            Some(bounds) => bounds,
+            None => return vec![],
+        };
+        // Rays start slightly above the surface so that they don't hit the
+        // triangles around the vertex.
+        let bias = (max - min).magnitude() * 1e-4;
+        let bvh = self.bvh();
+
+        self.positions
+            .iter()
+            .zip(self.normals.iter())
+            .enumerate()
+            .map(|(vertex, (&position, &normal))| {
+                if settings.sample_count == 0 || normal.magnitude2() == 0.0 {
+                    return 1.0;
+                }
+                let normal = normal.normalize();
+                let (tangent, bitangent) = tangent_frame(normal);
+                let origin = position + normal * bias;
+                let mut rng =
+                    SplitMix64::new(settings.seed ^ (vertex as u64).wrapping_mul(GOLDEN_GAMMA));
+
+                let unoccluded_count = (0..settings.sample_count)
+                    .filter(|_| {
+                        // Project a uniformly distributed point on the unit
+                        // disk up onto the hemisphere.
+                        let angle = 2.0 * PI * rng.next_f32();
+                        let r2 = rng.next_f32();
+                        let r = r2.sqrt();
+                        let direction = tangent * (r * angle.cos())
+                            + bitangent * (r * angle.sin())
+                            + normal * (1.0 - r2).sqrt();
+                        bvh.cast_ray(origin, direction, settings.max_distance)
+                            .is_none()
+                    })
+                    .count();
+                unoccluded_count as f32 / settings.sample_count as f32
+            })
+            .collect()
+    }
+}
+
+/// Returns two unit vectors that are perpendicular to each other and to the
+/// given unit normal.
+fn tangent_frame(normal: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let helper = if normal.x.abs() < 0.9 {
+        Vector3::new(1.0, 0.0, 0.0)
+    } else {
+        Vector3::new(0.0, 1.0, 0.0)
+    };
+    let tangent = normal.cross(helper).normalize();
+    (tangent, normal.cross(tangent))
+}
+
+/// The increment of the SplitMix64 generator, which is also used to spread
+/// out the seeds of consecutive vertices.
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// A small, fast pseudorandom number generator. It is not suitable for
+/// cryptography, but it is deterministic across platforms.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+    use cgmath::{Deg, Point3};
+
+    /// Returns a 2×2 floor facing +Z with a 2×2 wall standing along its +X
+    /// edge, facing the floor.
+    fn floor_and_wall() -> Mesh {
+        let mut mesh = MeshBuilder::plane(2.0, 2.0, 4, 4).build();
+        let mut wall = MeshBuilder::plane(2.0, 2.0, 4, 4).build();
+        wall.rotate(Vector3::new(0.0, 1.0, 0.0), Deg(-90.0));
+        wall.translate(Vector3::new(1.0, 0.0, 1.0));
+        mesh.append(&wall);
+        mesh
+    }
+
+    #[test]
+    fn same_seed_gives_same_results() {
+        let mesh = floor_and_wall();
+        let settings = AmbientOcclusionSettings {
+            sample_count: 16,
+            ..AmbientOcclusionSettings::default()
+        };
+        assert_eq!(
+            mesh.ambient_occlusion(&settings),
+            mesh.ambient_occlusion(&settings)
+        );
+        let reseeded = AmbientOcclusionSettings {
+            seed: 1,
+            ..settings
+        };
+        assert_ne!(
+            mesh.ambient_occlusion(&settings),
+            mesh.ambient_occlusion(&reseeded)
+        );
+    }
+
+    #[test]
+    fn convex_surfaces_are_unoccluded() {
+        let settings = AmbientOcclusionSettings::default();
+        let plane = MeshBuilder::plane(2.0, 2.0, 3, 3).build();
+        assert!(plane
+            .ambient_occlusion(&settings)
+            .iter()
+            .all(|&ao| ao == 1.0));
+        let cuboid = MeshBuilder::cuboid(Vector3::new(1.0, 2.0, 3.0), 2).build();
+        assert!(cuboid
+            .ambient_occlusion(&settings)
+            .iter()
+            .all(|&ao| ao == 1.0));
+    }
+
+    #[test]
+    fn inner_corner_is_occluded() {
+        let mesh = floor_and_wall();
+        let ao = mesh.ambient_occlusion(&AmbientOcclusionSettings::default());
+        let at = |p: Point3<f32>| ao[mesh.positions.iter().position(|&q| q == p).unwrap()];
+
+        // Near the wall, about half of the cosine-weighted hemisphere is
+        // blocked. Farther away, less of it is.
+        let near = at(Point3::new(0.5, 0.0, 0.0));
+        let far = at(Point3::new(-1.0, 0.0, 0.0));
+        assert!(near < 0.9, "{}", near);
+        assert!(far < 1.0 && far > near, "{} {}", far, near);
+
+        // Geometry beyond the maximum distance doesn't count.
+        let settings = AmbientOcclusionSettings {
+            max_distance: 0.1,
+            ..AmbientOcclusionSettings::default()
+        };
+        let ao = mesh.ambient_occlusion(&settings);
+        let index = mesh
+            .positions
+            .iter()
+            .position(|&q| q == Point3::new(-1.0, 0.0, 0.0))
+            .unwrap();
+        assert_eq!(ao[index], 1.0);
+    }
+
+    #[test]
+    fn bakes_into_an_attribute() {
+        let mut mesh = floor_and_wall();
+        let settings = AmbientOcclusionSettings {
+            sample_count: 8,
+            ..AmbientOcclusionSettings::default()
+        };
+        mesh.bake_ambient_occlusion(&settings);
+        assert_eq!(
+            mesh.attributes.get(AMBIENT_OCCLUSION_ATTRIBUTE),
+            Some(&AttributeData::Float(mesh.ambient_occlusion(&settings)))
+        );
+        assert!(Mesh::default().ambient_occlusion(&settings).is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+/// The values of a custom attribute, one for each vertex in a mesh.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AttributeData {
+    /// A floating point value for each vertex, such as stress or temperature.
+    Float(Vec<f32>),
+
+    /// An integer value for each vertex, such as a part or region ID.
+    Int(Vec<i32>),
+}
+
+impl AttributeData {
+    /// Returns the number of values in the attribute.
+    pub fn len(&self) -> usize {
+        match self {
+            AttributeData::Float(values) => values.len(),
+            AttributeData::Int(values) => values.len(),
+        }
+    }
+
+    /// Returns true if the attribute contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a new attribute of the same type that contains the values at
+    /// the given indices, in order.
+    pub(crate) fn select(&self, indices: &[usize]) -> Self {
+        match self {
+            AttributeData::Float(values) => {
+                AttributeData::Float(indices.iter().map(|&i| values[i]).collect())
+            }
+            AttributeData::Int(values) => {
+                AttributeData::Int(indices.iter().map(|&i| values[i]).collect())
+            }
+        }
+    }
+
+    /// Returns an attribute of the same type with `len` default values.
+    pub(crate) fn defaults_like(&self, len: usize) -> Self {
+        match self {
+            AttributeData::Float(_) => AttributeData::Float(vec![0.0; len]),
+            AttributeData::Int(_) => AttributeData::Int(vec![0; len]),
+        }
+    }
+
+    /// Appends the values of `other` to this attribute. If `other` has a
+    /// different type, then `len` default values are appended instead.
+    pub(crate) fn extend_from(&mut self, other: Option<&AttributeData>, len: usize) {
+        match (self, other) {
+            (AttributeData::Float(values), Some(AttributeData::Float(other_values))) => {
+                values.extend_from_slice(other_values)
+            }
+            (AttributeData::Int(values), Some(AttributeData::Int(other_values))) => {
+                values.extend_from_slice(other_values)
+            }
+            (AttributeData::Float(values), _) => values.resize(values.len() + len, 0.0),
+            (AttributeData::Int(values), _) => values.resize(values.len() + len, 0),
+        }
+    }
+}
@@ -0,0 +1,594 @@
+use super::{AttributeData, Error, Material, Mesh, Result};
+use cgmath::{Point2, Point3, Vector3, Vector4};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+// The native binary format stores a mesh as a sequence of little-endian 32-bit
+// words so that every array in it is 4-byte aligned and can be borrowed in
+// place:
+//
+// - The magic bytes `RMSH`.
+// - The format version.
+// - Flags that indicate which optional attributes are present.
+// - The number of vertices, triangles, and custom attributes.
+// - Positions, normals, and any UVs, tangents, and colors, as `f32`s.
+// - Triangle vertex indices as `u32`s.
+// - For each custom attribute, in order of name: the length of the name in
+//   bytes, the name padded with zeros to a multiple of four bytes, the type
+//   (0 for float and 1 for int), and the values.
+// - Since version 2, the number of materials, and for each material: its name,
+//   stored like an attribute's name, and its base color as four `f32`s.
+// - Since version 2, if flagged, the index of each triangle's material as a
+//   `u32`.
+// - A CRC-32 checksum of all of the preceding bytes.
+
+const MAGIC: [u8; 4] = *b"RMSH";
+const VERSION: u32 = 2;
+const MIN_VERSION: u32 = 1;
+const HEADER_WORDS: usize = 6;
+
+const HAS_UVS: u32 = 1;
+const HAS_TANGENTS: u32 = 1 << 1;
+const HAS_COLORS: u32 = 1 << 2;
+const HAS_TRIANGLE_MATERIALS: u32 = 1 << 3;
+
+const FLOAT_ATTRIBUTE: u32 = 0;
+const INT_ATTRIBUTE: u32 = 1;
+
+/// A read-only view of a mesh that borrows its arrays directly from a buffer
+/// in the native binary format, without copying them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshView<'a> {
+    pub positions: &'a [[f32; 3]],
+    pub normals: &'a [[f32; 3]],
+    pub uvs: Option<&'a [[f32; 2]]>,
+    pub tangents: Option<&'a [[f32; 4]]>,
+    pub colors: Option<&'a [[f32; 4]]>,
+    pub attributes: Vec<(&'a str, AttributeView<'a>)>,
+    pub triangle_vertex_indices: &'a [[u32; 3]],
+    pub materials: Vec<(&'a str, [f32; 4])>,
+    pub triangle_materials: Option<&'a [u32]>,
+}
+
+/// The values of a custom attribute in a [`MeshView`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttributeView<'a> {
+    Float(&'a [f32]),
+    Int(&'a [i32]),
+}
+
+impl<'a> MeshView<'a> {
+    /// Borrows a mesh from a buffer in the native binary format. The buffer
+    /// must be 4-byte aligned, which is the case for memory-mapped files and
+    /// for buffers allocated as `u32`s. The checksum is verified before the
+    /// view is returned.
+    ///
+    /// The format is little-endian, so this is not supported on big-endian
+    /// targets.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self> {
+        if cfg!(target_endian = "big") {
+            return Err(Error::UnsupportedPlatform);
+        }
+        if bytes.len() < 4 * (HEADER_WORDS + 1) || bytes[0..4] != MAGIC {
+            return Err(Error::NotMeshBinary);
+        }
+        if bytes.len() % 4 != 0 {
+            return Err(Error::MalformedMeshBinary);
+        }
+        let words: &[u32] =
+            bytemuck::try_cast_slice(bytes).map_err(|_| Error::MisalignedMeshBinary)?;
+
+        let (checksum, words) = words.split_last().ok_or(Error::MalformedMeshBinary)?;
+        if crc32fast::hash(&bytes[..bytes.len() - 4]) != *checksum {
+            return Err(Error::ChecksumMismatch);
+        }
+
+        let version = words[1];
+        if !(MIN_VERSION..=VERSION).contains(&version) {
+            return Err(Error::UnsupportedMeshBinaryVersion(version));
+        }
+        let flags = words[2];
+        let vertex_count = words[3] as usize;
+        let triangle_count = words[4] as usize;
+        let attribute_count = words[5] as usize;
+
+        let mut reader = WordReader {
+            words,
+            position: HEADER_WORDS,
+        };
+
+        let positions = bytemuck::cast_slice(reader.take(3 * vertex_count)?);
+        let normals = bytemuck::cast_slice(reader.take(3 * vertex_count)?);
+        let uvs = if flags & HAS_UVS != 0 {
+            Some(bytemuck::cast_slice(reader.take(2 * vertex_count)?))
+        } else {
+            None
+        };
+        let tangents = if flags & HAS_TANGENTS != 0 {
+            Some(bytemuck::cast_slice(reader.take(4 * vertex_count)?))
+        } else {
+            None
+        };
+        let colors = if flags & HAS_COLORS != 0 {
+            Some(bytemuck::cast_slice(reader.take(4 * vertex_count)?))
+        } else {
+            None
+        };
+        let triangle_vertex_indices: &[[u32; 3]] =
+            bytemuck::cast_slice(reader.take(3 * triangle_count)?);
+        if triangle_vertex_indices
+            .iter()
+            .flatten()
+            .any(|&i| i as usize >= vertex_count)
+        {
+            return Err(Error::MalformedMeshBinary);
+        }
+
+        let mut attributes = Vec::with_capacity(attribute_count);
+        for _ in 0..attribute_count {
+            let name = reader.take_name()?;
+            let kind = reader.take(1)?[0];
+            let values = reader.take(vertex_count)?;
+            let values = match kind {
+                FLOAT_ATTRIBUTE => AttributeView::Float(bytemuck::cast_slice(values)),
+                INT_ATTRIBUTE => AttributeView::Int(bytemuck::cast_slice(values)),
+                _ => return Err(Error::MalformedMeshBinary),
+            };
+            attributes.push((name, values));
+        }
+
+        let mut materials = vec![];
+        let mut triangle_materials = None;
+        if version >= 2 {
+            let material_count = reader.take(1)?[0] as usize;
+            for _ in 0..material_count {
+                let name = reader.take_name()?;
+                let base_color: &[f32] = bytemuck::cast_slice(reader.take(4)?);
+                materials.push((
+                    name,
+                    [base_color[0], base_color[1], base_color[2], base_color[3]],
+                ));
+            }
+            if flags & HAS_TRIANGLE_MATERIALS != 0 {
+                let indices = reader.take(triangle_count)?;
+                if indices.iter().any(|&m| m as usize >= material_count) {
+                    return Err(Error::MalformedMeshBinary);
+                }
+                triangle_materials = Some(indices);
+            }
+        }
+
+        if reader.position != reader.words.len() {
+            return Err(Error::MalformedMeshBinary);
+        }
+
+        Ok(MeshView {
+            positions,
+            normals,
+            uvs,
+            tangents,
+            colors,
+            attributes,
+            triangle_vertex_indices,
+            materials,
+            triangle_materials,
+        })
+    }
+
+    /// Copies the view into an owned mesh.
+    pub fn to_mesh(&self) -> Mesh {
+        Mesh {
+            positions: self.positions.iter().map(|&p| Point3::from(p)).collect(),
+            normals: self.normals.iter().map(|&n| Vector3::from(n)).collect(),
+            uvs: self
+                .uvs
+                .map(|uvs| uvs.iter().map(|&uv| Point2::from(uv)).collect()),
+            tangents: self
+                .tangents
+                .map(|tangents| tangents.iter().map(|&t| Vector4::from(t)).collect()),
+            colors: self
+                .colors
+                .map(|colors| colors.iter().map(|&c| Vector4::from(c)).collect()),
+            attributes: self
+                .attributes
+                .iter()
+                .map(|&(name, values)| {
+                    let data = match values {
+                        AttributeView::Float(values) => AttributeData::Float(values.to_vec()),
+                        AttributeView::Int(values) => AttributeData::Int(values.to_vec()),
+                    };
+                    (name.to_string(), data)
+                })
+                .collect::<HashMap<_, _>>(),
+            triangle_vertex_indices: self
+                .triangle_vertex_indices
+                .iter()
+                .map(|&[i1, i2, i3]| (i1 as usize, i2 as usize, i3 as usize))
+                .collect(),
+            materials: self
+                .materials
+                .iter()
+                .map(|&(name, base_color)| Material::new(name, Vector4::from(base_color)))
+                .collect(),
+            triangle_materials: self
+                .triangle_materials
+                .map(|materials| materials.iter().map(|&m| m as usize).collect()),
+        }
+    }
+}
+
+impl Mesh {
+    /// Writes the mesh in the native binary format, which is much faster to
+    /// load than STL. Meshes with more than `u32::MAX` vertices or triangles
+    /// cannot be written.
+    ///
+    /// Returns [`Error::InconsistentMesh`] without writing anything if a
+    /// vertex array or attribute does not have a value for each vertex, if
+    /// `triangle_materials` does not have a value for each triangle, or if an
+    /// index is out of range, since the file could not be read back.
+    pub fn write_binary<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.check_consistency()?;
+
+        let to_u32 = |n: usize| u32::try_from(n).map_err(|_| Error::MeshTooLargeForBinary);
+        let vertex_count = to_u32(self.positions.len())?;
+
+        let mut flags = 0;
+        if self.uvs.is_some() {
+            flags |= HAS_UVS;
+        }
+        if self.tangents.is_some() {
+            flags |= HAS_TANGENTS;
+        }
+        if self.colors.is_some() {
+            flags |= HAS_COLORS;
+        }
+        if self.triangle_materials.is_some() {
+            flags |= HAS_TRIANGLE_MATERIALS;
+        }
+
+        let mut words: Vec<u32> = vec![
+            u32::from_le_bytes(MAGIC),
+            VERSION,
+            flags,
+            vertex_count,
+            to_u32(self.triangle_vertex_indices.len())?,
+            to_u32(self.attributes.len())?,
+        ];
+
+        for p in &self.positions {
+            push_floats(&mut words, &[p.x, p.y, p.z]);
+        }
+        for n in &self.normals {
+            push_floats(&mut words, &[n.x, n.y, n.z]);
+        }
+        for uv in self.uvs.iter().flatten() {
+            push_floats(&mut words, &[uv.x, uv.y]);
+        }
+        for t in self.tangents.iter().flatten() {
+            push_floats(&mut words, &[t.x, t.y, t.z, t.w]);
+        }
+        for c in self.colors.iter().flatten() {
+            push_floats(&mut words, &[c.x, c.y, c.z, c.w]);
+        }
+        for &(i1, i2, i3) in &self.triangle_vertex_indices {
+            words.extend_from_slice(&[to_u32(i1)?, to_u32(i2)?, to_u32(i3)?]);
+        }
+
+        let mut names: Vec<&String> = self.attributes.keys().collect();
+        names.sort();
+        for name in names {
+            push_name(&mut words, name)?;
+
+            match &self.attributes[name] {
+                AttributeData::Float(values) => {
+                    words.push(FLOAT_ATTRIBUTE);
+                    push_floats(&mut words, values);
+                }
+                AttributeData::Int(values) => {
+                    words.push(INT_ATTRIBUTE);
+                    words.extend(values.iter().map(|&v| v as u32));
+                }
+            }
+        }
+
+        words.push(to_u32(self.materials.len())?);
+        for material in &self.materials {
+            push_name(&mut words, &material.name)?;
+            let c = material.base_color;
+            push_floats(&mut words, &[c.x, c.y, c.z, c.w]);
+        }
+        for &material in self.triangle_materials.iter().flatten() {
+            words.push(to_u32(material)?);
+        }
+
+        let mut bytes = Vec::with_capacity(4 * (words.len() + 1));
+        for word in words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        let checksum = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Reads a mesh in the native binary format. To avoid copying the mesh's
+    /// arrays, memory-map the file and use [`MeshView::from_bytes`] instead.
+    pub fn read_binary<R: Read>(reader: &mut R) -> Result<Mesh> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+
+        // Copy the bytes into a buffer of words so that they are aligned.
+        let mut words = vec![0u32; bytes.len().div_ceil(4)];
+        let aligned: &mut [u8] = bytemuck::cast_slice_mut(&mut words);
+        aligned[..bytes.len()].copy_from_slice(&bytes);
+
+        Ok(MeshView::from_bytes(&aligned[..bytes.len()])?.to_mesh())
+    }
+}
+
+impl Mesh {
+    /// Returns an error if the mesh's arrays do not match its vertex and
+    /// triangle counts or if any index is out of range.
+    fn check_consistency(&self) -> Result<()> {
+        let vertex_count = self.positions.len();
+        let triangle_count = self.triangle_vertex_indices.len();
+        let vertex_array_lens = [
+            Some(self.normals.len()),
+            self.uvs.as_ref().map(Vec::len),
+            self.tangents.as_ref().map(Vec::len),
+            self.colors.as_ref().map(Vec::len),
+        ];
+        let has_value_per_vertex = vertex_array_lens
+            .iter()
+            .flatten()
+            .copied()
+            .chain(self.attributes.values().map(AttributeData::len))
+            .all(|len| len == vertex_count);
+        let has_valid_triangles = self
+            .triangle_vertex_indices
+            .iter()
+            .all(|&(i1, i2, i3)| i1.max(i2).max(i3) < vertex_count);
+        let has_valid_materials = self.triangle_materials.as_ref().map_or(true, |materials| {
+            materials.len() == triangle_count && materials.iter().all(|&m| m < self.materials.len())
+        });
+
+        if has_value_per_vertex && has_valid_triangles && has_valid_materials {
+            Ok(())
+        } else {
+            Err(Error::InconsistentMesh)
+        }
+    }
+}
+
+fn push_floats(words: &mut Vec<u32>, values: &[f32]) {
+    words.extend(values.iter().map(|v| v.to_bits()));
+}
+
+/// Pushes the length of the name in bytes, followed by the name padded with
+/// zeros to a whole number of words.
+fn push_name(words: &mut Vec<u32>, name: &str) -> Result<()> {
+    let mut name_bytes = name.as_bytes().to_vec();
+    words.push(u32::try_from(name_bytes.len()).map_err(|_| Error::MeshTooLargeForBinary)?);
+    name_bytes.resize(name_bytes.len().div_ceil(4) * 4, 0);
+    words.extend(
+        name_bytes
+            .chunks(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+    );
+    Ok(())
+}
+
+/// Reads consecutive runs of words from a buffer.
+struct WordReader<'a> {
+    words: &'a [u32],
+    position: usize,
+}
+
+impl<'a> WordReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u32]> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.words.len())
+            .ok_or(Error::MalformedMeshBinary)?;
+        let words = &self.words[self.position..end];
+        self.position = end;
+        Ok(words)
+    }
+
+    /// Reads a name that was written by `push_name`.
+    fn take_name(&mut self) -> Result<&'a str> {
+        let name_len = self.take(1)?[0] as usize;
+        let name_bytes: &[u8] = bytemuck::cast_slice(self.take(name_len.div_ceil(4))?);
+        std::str::from_utf8(&name_bytes[..name_len]).map_err(|_| Error::MalformedMeshBinary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+
+    /// A tetrahedron with every optional array filled in.
+    fn full_mesh() -> Mesh {
+        let positions = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::new(0.0, 0.0, 1.0),
+        ];
+        let normals = positions
+            .iter()
+            .map(|p| Vector3::new(p.x, p.y, p.z + 0.5))
+            .collect();
+        MeshBuilder::new(
+            positions,
+            normals,
+            vec![(0, 2, 1), (0, 1, 3), (0, 3, 2), (1, 2, 3)],
+        )
+        .uvs((0..4).map(|i| Point2::new(i as f32, 0.5)).collect())
+        .tangents(
+            (0..4)
+                .map(|i| Vector4::new(1.0, 0.0, i as f32, -1.0))
+                .collect(),
+        )
+        .colors(
+            (0..4)
+                .map(|i| Vector4::new(0.25, 0.5, i as f32, 1.0))
+                .collect(),
+        )
+        .attribute("stress", AttributeData::Float(vec![0.5, 1.5, -2.0, 3.0]))
+        .attribute("part", AttributeData::Int(vec![7, -1, 0, 12]))
+        .materials(
+            vec![
+                Material::new("steel", Vector4::new(0.5, 0.5, 0.5, 1.0)),
+                Material::new("paint", Vector4::new(1.0, 0.0, 0.0, 1.0)),
+            ],
+            vec![0, 1, 1, 0],
+        )
+        .build()
+    }
+
+    fn to_bytes(mesh: &Mesh) -> Vec<u8> {
+        let mut bytes = vec![];
+        mesh.write_binary(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn assert_meshes_equal(actual: &Mesh, expected: &Mesh) {
+        assert_eq!(actual.positions, expected.positions);
+        assert_eq!(actual.normals, expected.normals);
+        assert_eq!(actual.uvs, expected.uvs);
+        assert_eq!(actual.tangents, expected.tangents);
+        assert_eq!(actual.colors, expected.colors);
+        assert_eq!(actual.attributes, expected.attributes);
+        assert_eq!(
+            actual.triangle_vertex_indices,
+            expected.triangle_vertex_indices
+        );
+        assert_eq!(actual.materials, expected.materials);
+        assert_eq!(actual.triangle_materials, expected.triangle_materials);
+    }
+
+    #[test]
+    fn round_trips_mesh_with_every_array() {
+        let mesh = full_mesh();
+        let read = Mesh::read_binary(&mut to_bytes(&mesh).as_slice()).unwrap();
+        assert_meshes_equal(&read, &mesh);
+    }
+
+    #[test]
+    fn round_trips_mesh_without_optional_arrays() {
+        let mesh = MeshBuilder::cuboid(Vector3::new(1.0, 2.0, 3.0), 1).build();
+        let mesh = Mesh { uvs: None, ..mesh };
+        let read = Mesh::read_binary(&mut to_bytes(&mesh).as_slice()).unwrap();
+        assert_meshes_equal(&read, &mesh);
+    }
+
+    #[test]
+    fn round_trips_empty_mesh() {
+        let mesh = Mesh::default();
+        let read = Mesh::read_binary(&mut to_bytes(&mesh).as_slice()).unwrap();
+        assert_meshes_equal(&read, &mesh);
+    }
+
+    #[test]
+    fn rejects_flipped_payload_byte() {
+        let mut bytes = to_bytes(&full_mesh());
+        // A byte inside the positions.
+        bytes[4 * HEADER_WORDS + 5] ^= 0x10;
+        assert!(matches!(
+            Mesh::read_binary(&mut bytes.as_slice()),
+            Err(Error::ChecksumMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_buffer() {
+        let bytes = to_bytes(&full_mesh());
+        for len in [0, 3, 4 * HEADER_WORDS, bytes.len() - 5, bytes.len() - 4] {
+            assert!(
+                Mesh::read_binary(&mut &bytes[..len]).is_err(),
+                "read {} of {} bytes",
+                len,
+                bytes.len()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_inconsistent_mesh_before_writing() {
+        let short_normals = Mesh {
+            normals: vec![],
+            ..full_mesh()
+        };
+        let short_attribute = Mesh {
+            attributes: std::iter::once(("stress".to_string(), AttributeData::Float(vec![1.0])))
+                .collect(),
+            ..full_mesh()
+        };
+        let short_triangle_materials = Mesh {
+            triangle_materials: Some(vec![0]),
+            ..full_mesh()
+        };
+        let missing_material = Mesh {
+            triangle_materials: Some(vec![0, 1, 2, 0]),
+            ..full_mesh()
+        };
+        let out_of_range_vertex = Mesh {
+            triangle_vertex_indices: vec![(0, 1, 4)],
+            triangle_materials: None,
+            ..full_mesh()
+        };
+
+        for mesh in [
+            short_normals,
+            short_attribute,
+            short_triangle_materials,
+            missing_material,
+            out_of_range_vertex,
+        ] {
+            let mut bytes = vec![];
+            assert!(matches!(
+                mesh.write_binary(&mut bytes),
+                Err(Error::InconsistentMesh)
+            ));
+            assert!(bytes.is_empty());
+        }
+    }
+
+    #[test]
+    fn reads_version_1() {
+        let mesh = Mesh {
+            materials: vec![],
+            triangle_materials: None,
+            ..full_mesh()
+        };
+        let bytes = to_bytes(&mesh);
+
+        // Version 1 ended with the custom attributes, so drop the material
+        // count and the checksum, and then checksum the rest again.
+        let mut v1 = bytes[..bytes.len() - 8].to_vec();
+        v1[4..8].copy_from_slice(&1u32.to_le_bytes());
+        let checksum = crc32fast::hash(&v1);
+        v1.extend_from_slice(&checksum.to_le_bytes());
+
+        let read = Mesh::read_binary(&mut v1.as_slice()).unwrap();
+        assert_meshes_equal(&read, &mesh);
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut bytes = to_bytes(&full_mesh());
+        let len = bytes.len();
+        bytes[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        let checksum = crc32fast::hash(&bytes[..len - 4]);
+        bytes[len - 4..].copy_from_slice(&checksum.to_le_bytes());
+        assert!(matches!(
+            Mesh::read_binary(&mut bytes.as_slice()),
+            Err(Error::UnsupportedMeshBinaryVersion(v)) if v == VERSION + 1
+        ));
+    }
+}
@@ -0,0 +1,249 @@
+use super::Mesh;
+use cgmath::{Point3, Vector3};
+
+/// The maximum number of triangles in a leaf node.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// A bounding volume hierarchy over the triangles of a mesh. Building one takes
+/// `O(n log n)` time for a mesh with `n` triangles, after which spatial queries
+/// against the mesh take roughly `O(log n)` time. Build it once and reuse it
+/// when making many queries against the same mesh.
+#[derive(Debug, Clone)]
+pub struct Bvh<'msh> {
+    pub(crate) mesh: &'msh Mesh,
+
+    /// The nodes of the tree in depth-first order. The root is the first node,
+    /// and the left child of an interior node immediately follows it.
+    pub(crate) nodes: Vec<BvhNode>,
+
+    /// Indices of the mesh's triangles, ordered so that each leaf node refers
+    /// to a contiguous range.
+    pub(crate) triangle_indices: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BvhNode {
+    pub(crate) bounds: Aabb,
+    pub(crate) kind: BvhNodeKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum BvhNodeKind {
+    /// A leaf that contains `triangle_indices[start..start + count]`.
+    Leaf { start: usize, count: usize },
+
+    /// An interior node whose left child is the next node and whose right child
+    /// is at the given index.
+    Interior { right: usize },
+}
+
+impl Mesh {
+    /// Builds a bounding volume hierarchy over the mesh's triangles.
+    pub fn bvh(&self) -> Bvh<'_> {
+        Bvh::new(self)
+    }
+}
+
+impl<'msh> Bvh<'msh> {
+    pub fn new(mesh: &'msh Mesh) -> Self {
+        let triangle_count = mesh.triangle_vertex_indices.len();
+        let mut bvh = Bvh {
+            mesh,
+            nodes: Vec::with_capacity(2 * triangle_count / MAX_LEAF_SIZE + 1),
+            triangle_indices: (0..triangle_count).collect(),
+        };
+
+        let bounds: Vec<Aabb> = mesh
+            .triangles_iter()
+            .map(|t| {
+                let (p1, p2, p3) = t.positions();
+                Aabb::from_points(&[p1, p2, p3])
+            })
+            .collect();
+        let centroids: Vec<Point3<f32>> = bounds.iter().map(Aabb::center).collect();
+
+        if triangle_count > 0 {
+            bvh.build(&bounds, &centroids, 0, triangle_count);
+        }
+        bvh
+    }
+
+    /// Returns the mesh that the hierarchy was built over.
+    pub fn mesh(&self) -> &'msh Mesh {
+        self.mesh
+    }
+
+    /// Returns the indices of the triangles whose bounding boxes overlap the
+    /// given box, in no particular order.
+    pub(crate) fn overlapping_triangles(&self, bounds: &Aabb) -> Vec<usize> {
+        let mut triangles = vec![];
+        if self.nodes.is_empty() {
+            return triangles;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !node.bounds.overlaps(bounds) {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { start, count } => {
+                    triangles.extend_from_slice(&self.triangle_indices[start..start + count])
+                }
+                BvhNodeKind::Interior { right } => {
+                    stack.push(node_index + 1);
+                    stack.push(right);
+                }
+            }
+        }
+        triangles
+    }
+
+    /// Recursively builds the subtree over `triangle_indices[start..end]` and
+    /// returns the index of its root node.
+    fn build(
+        &mut self,
+        bounds: &[Aabb],
+        centroids: &[Point3<f32>],
+        start: usize,
+        end: usize,
+    ) -> usize {
+        let node_bounds = self.triangle_indices[start..end]
+            .iter()
+            .fold(Aabb::empty(), |acc, &t| acc.union(&bounds[t]));
+        let node_index = self.nodes.len();
+
+        let count = end - start;
+        if count <= MAX_LEAF_SIZE {
+            self.nodes.push(BvhNode {
+                bounds: node_bounds,
+                kind: BvhNodeKind::Leaf { start, count },
+            });
+            return node_index;
+        }
+
+        // Split at the median centroid along the axis in which the centroids
+        // are most spread out.
+        let centroid_bounds = self.triangle_indices[start..end]
+            .iter()
+            .fold(Aabb::empty(), |acc, &t| acc.including(centroids[t]));
+        let axis = centroid_bounds.longest_axis();
+        let mid = start + count / 2;
+        self.triangle_indices[start..end].select_nth_unstable_by(count / 2, |&a, &b| {
+            centroids[a][axis]
+                .partial_cmp(&centroids[b][axis])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        self.nodes.push(BvhNode {
+            bounds: node_bounds,
+            kind: BvhNodeKind::Interior { right: 0 },
+        });
+        self.build(bounds, centroids, start, mid);
+        let right = self.build(bounds, centroids, mid, end);
+        self.nodes[node_index].kind = BvhNodeKind::Interior { right };
+        node_index
+    }
+}
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Aabb {
+    pub(crate) min: Point3<f32>,
+    pub(crate) max: Point3<f32>,
+}
+
+impl Aabb {
+    /// Returns a box that contains nothing. The union of an empty box with any
+    /// other box is the other box.
+    pub(crate) fn empty() -> Self {
+        Aabb {
+            min: Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub(crate) fn from_points(points: &[Point3<f32>]) -> Self {
+        points
+            .iter()
+            .fold(Aabb::empty(), |acc, &p| acc.including(p))
+    }
+
+    pub(crate) fn including(&self, p: Point3<f32>) -> Self {
+        Aabb {
+            min: Point3::new(
+                self.min.x.min(p.x),
+                self.min.y.min(p.y),
+                self.min.z.min(p.z),
+            ),
+            max: Point3::new(
+                self.max.x.max(p.x),
+                self.max.y.max(p.y),
+                self.max.z.max(p.z),
+            ),
+        }
+    }
+
+    pub(crate) fn union(&self, other: &Aabb) -> Self {
+        self.including(other.min).including(other.max)
+    }
+
+    /// Returns true if the boxes overlap or touch.
+    pub(crate) fn overlaps(&self, other: &Aabb) -> bool {
+        (0..3).all(|axis| self.min[axis] <= other.max[axis] && other.min[axis] <= self.max[axis])
+    }
+
+    pub(crate) fn center(&self) -> Point3<f32> {
+        self.min + (self.max - self.min) / 2.0
+    }
+
+    /// Returns the index of the axis along which the box is longest.
+    fn longest_axis(&self) -> usize {
+        let d = self.max - self.min;
+        if d.x >= d.y && d.x >= d.z {
+            0
+        } else if d.y >= d.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Returns the distance along a ray at which it enters the box, or zero if
+    /// its origin is inside the box, or `None` if it misses the box. The ray is
+    /// given by its origin and the reciprocal of its normalized direction.
+    pub(crate) fn ray_entry(
+        &self,
+        origin: Point3<f32>,
+        inverse_direction: Vector3<f32>,
+    ) -> Option<f32> {
+        let mut entry = 0.0f32;
+        let mut exit = f32::INFINITY;
+        for axis in 0..3 {
+            let t1 = (self.min[axis] - origin[axis]) * inverse_direction[axis];
+            let t2 = (self.max[axis] - origin[axis]) * inverse_direction[axis];
+            // A zero direction component with the origin on the slab's
+            // boundary gives NaN, which `min` and `max` ignore, so the ray
+            // counts as inside the slab.
+            entry = entry.max(t1.min(t2));
+            exit = exit.min(t1.max(t2));
+        }
+        if entry <= exit {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the squared distance from the point to the closest point in the
+    /// box, or zero if the point is inside the box.
+    pub(crate) fn distance2(&self, p: Point3<f32>) -> f32 {
+        let d = Vector3::new(
+            (self.min.x - p.x).max(0.0).max(p.x - self.max.x),
+            (self.min.y - p.y).max(0.0).max(p.y - self.max.y),
+            (self.min.z - p.z).max(0.0).max(p.z - self.max.z),
+        );
+        d.x * d.x + d.y * d.y + d.z * d.z
+    }
+}
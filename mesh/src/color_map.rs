@@ -0,0 +1,74 @@
+use super::Mesh;
+use cgmath::Vector4;
+
+/// The colors at evenly spaced points along the color map, from low values to
+/// high values.
+const COLOR_MAP_STOPS: [[f32; 3]; 5] = [
+    [0.05, 0.15, 0.85],
+    [0.0, 0.7, 0.9],
+    [0.1, 0.8, 0.2],
+    [0.95, 0.85, 0.1],
+    [0.9, 0.1, 0.05],
+];
+
+/// Maps a value to an opaque linear RGBA color that ranges from blue at `min`
+/// through cyan, green, and yellow to red at `max`. Values outside the range
+/// are clamped, and NaN maps to the color for `min`.
+pub fn color_map(value: f32, min: f32, max: f32) -> Vector4<f32> {
+    let t = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let t = if t.is_nan() { 0.0 } else { t };
+
+    let scaled = t * (COLOR_MAP_STOPS.len() - 1) as f32;
+    let i = (scaled as usize).min(COLOR_MAP_STOPS.len() - 2);
+    let f = scaled - i as f32;
+    let (lo, hi) = (COLOR_MAP_STOPS[i], COLOR_MAP_STOPS[i + 1]);
+    Vector4::new(
+        lo[0] + (hi[0] - lo[0]) * f,
+        lo[1] + (hi[1] - lo[1]) * f,
+        lo[2] + (hi[2] - lo[2]) * f,
+        1.0,
+    )
+}
+
+impl Mesh {
+    /// Converts a value for each triangle into a value for each vertex by
+    /// averaging the values of the triangles around each vertex, weighted by
+    /// their areas. Vertices that are not used by any triangle get zero.
+    ///
+    /// Meshes that store separate vertices for each triangle, such as meshes
+    /// loaded from STL files, keep exactly the triangle's value at each of its
+    /// vertices, so per-triangle values are shown without blurring.
+    pub fn face_values_to_vertex_values(&self, face_values: &[f32]) -> Vec<f32> {
+        let mut sums = vec![0.0; self.positions.len()];
+        let mut weights = vec![0.0; self.positions.len()];
+        for (t, &value) in self.triangles_iter().zip(face_values) {
+            // Give degenerate triangles a tiny weight so that vertices that
+            // only belong to them still get a value.
+            let weight = t.area().max(f32::MIN_POSITIVE);
+            let (i1, i2, i3) = t.vertex_indices();
+            for &i in &[i1, i2, i3] {
+                sums[i] += value * weight;
+                weights[i] += weight;
+            }
+        }
+
+        sums.iter()
+            .zip(weights)
+            .map(|(&sum, weight)| if weight > 0.0 { sum / weight } else { 0.0 })
+            .collect()
+    }
+
+    /// Replaces the mesh's vertex colors with a visualization of a value for
+    /// each vertex, using [`color_map`] over the range from `min` to `max`.
+    pub fn set_colors_from_values(&mut self, vertex_values: &[f32], min: f32, max: f32) {
+        let colors = vertex_values
+            .iter()
+            .map(|&value| color_map(value, min, max))
+            .collect();
+        self.colors = Some(colors);
+    }
+}
@@ -0,0 +1,268 @@
+use super::{AttributeData, Bvh, Mesh};
+use cgmath::{EuclideanSpace, InnerSpace, Point3};
+
+/// The name of the custom attribute in which [`Mesh::bake_deviation`] stores
+/// its results.
+pub const DEVIATION_ATTRIBUTE: &str = "deviation";
+
+/// Settings for comparing meshes with [`Mesh::compare`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComparisonSettings {
+    /// The number of segments that each edge of each triangle is split into
+    /// when sampling the surface. Each triangle gets `(n + 1) * (n + 2) / 2`
+    /// samples, including its vertices, so `1` samples only the vertices.
+    /// Larger values find deviations inside large triangles, such as those of
+    /// CAD models, more reliably.
+    pub subdivisions: usize,
+}
+
+impl Default for ComparisonSettings {
+    fn default() -> Self {
+        ComparisonSettings { subdivisions: 4 }
+    }
+}
+
+/// How far a mesh deviates from a reference mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshComparison {
+    /// The signed distance from each vertex of the mesh to the reference
+    /// mesh's surface. Vertices outside the reference are positive and
+    /// vertices inside are negative.
+    pub vertex_deviations: Vec<f32>,
+
+    /// The one-sided Hausdorff distance from the mesh to the reference, which
+    /// is the greatest distance from any point on the mesh to the reference's
+    /// surface.
+    pub hausdorff_distance: f32,
+
+    /// The one-sided Hausdorff distance from the reference to the mesh, which
+    /// is the greatest distance from any point on the reference to the mesh's
+    /// surface.
+    pub reference_hausdorff_distance: f32,
+
+    /// The root mean square of the distance from the mesh to the reference's
+    /// surface, weighted by area over the mesh's surface.
+    pub rms_deviation: f32,
+}
+
+impl MeshComparison {
+    /// Returns the symmetric Hausdorff distance, which is the greater of the
+    /// two one-sided distances.
+    pub fn symmetric_hausdorff_distance(&self) -> f32 {
+        self.hausdorff_distance
+            .max(self.reference_hausdorff_distance)
+    }
+}
+
+impl Mesh {
+    /// Measures how far the mesh deviates from the reference mesh, such as a
+    /// scan from its nominal CAD geometry. Returns `None` if either mesh has no
+    /// triangles.
+    ///
+    /// Distances are measured from samples spread over each surface, so the
+    /// Hausdorff distances and RMS deviation are estimates that improve with
+    /// more subdivisions. The signs of the vertex deviations are determined as
+    /// in [`SignedDistanceField`](super::SignedDistanceField), so they are
+    /// only meaningful if the reference is closed and consistently oriented.
+    pub fn compare(
+        &self,
+        reference: &Mesh,
+        settings: &ComparisonSettings,
+    ) -> Option<MeshComparison> {
+        if self.triangle_vertex_indices.is_empty() || reference.triangle_vertex_indices.is_empty() {
+            return None;
+        }
+
+        let (hausdorff_distance, rms_deviation) =
+            self.sampled_distances(&reference.bvh(), settings)?;
+        let (reference_hausdorff_distance, _) =
+            reference.sampled_distances(&self.bvh(), settings)?;
+
+        Some(MeshComparison {
+            vertex_deviations: self.vertex_deviations(reference)?,
+            hausdorff_distance,
+            reference_hausdorff_distance,
+            rms_deviation,
+        })
+    }
+
+    /// Returns the one-sided Hausdorff distance from this mesh to the other
+    /// mesh, or `None` if either mesh has no triangles. See
+    /// [`Mesh::compare`].
+    pub fn hausdorff_distance(&self, other: &Mesh, settings: &ComparisonSettings) -> Option<f32> {
+        if other.triangle_vertex_indices.is_empty() {
+            return None;
+        }
+        let (max, _) = self.sampled_distances(&other.bvh(), settings)?;
+        Some(max)
+    }
+
+    /// Returns the symmetric Hausdorff distance between this mesh and the
+    /// other mesh, which is the greater of the two one-sided distances, or
+    /// `None` if either mesh has no triangles.
+    pub fn symmetric_hausdorff_distance(
+        &self,
+        other: &Mesh,
+        settings: &ComparisonSettings,
+    ) -> Option<f32> {
+        Some(
+            self.hausdorff_distance(other, settings)?
+                .max(other.hausdorff_distance(self, settings)?),
+        )
+    }
+
+    /// Returns the signed distance from each vertex to the reference mesh's
+    /// surface, or `None` if the reference has no triangles. See
+    /// [`MeshComparison::vertex_deviations`].
+    pub fn vertex_deviations(&self, reference: &Mesh) -> Option<Vec<f32>> {
+        let field = reference.signed_distance_field();
+        self.positions
+            .iter()
+            .map(|&p| field.signed_distance(p))
+            .collect()
+    }
+
+    /// Computes the signed distance from each vertex to the reference mesh's
+    /// surface and stores it as a float attribute named
+    /// [`DEVIATION_ATTRIBUTE`], replacing any previous values. Does nothing if
+    /// the reference has no triangles.
+    ///
+    /// Pass the values to [`Mesh::set_colors_from_values`] with a range that
+    /// is symmetric about zero to color the mesh by deviation.
+    pub fn bake_deviation(&mut self, reference: &Mesh) {
+        if let Some(values) = self.vertex_deviations(reference) {
+            self.attributes.insert(
+                DEVIATION_ATTRIBUTE.to_string(),
+                AttributeData::Float(values),
+            );
+        }
+    }
+
+    /// Samples the mesh's surface and measures the distance from each sample
+    /// to the surface in the BVH. Returns the greatest distance and the
+    /// area-weighted RMS distance, or `None` if the mesh has no triangles.
+    fn sampled_distances(&self, other: &Bvh, settings: &ComparisonSettings) -> Option<(f32, f32)> {
+        let n = settings.subdivisions.max(1);
+        let samples_per_triangle = ((n + 1) * (n + 2) / 2) as f32;
+
+        let mut max_distance: Option<f32> = None;
+        let mut weighted_square_sum = 0.0f64;
+        let mut total_weight = 0.0f64;
+        for &(i1, i2, i3) in &self.triangle_vertex_indices {
+            let (a, b, c) = (self.positions[i1], self.positions[i2], self.positions[i3]);
+            let area = (b - a).cross(c - a).magnitude() / 2.0;
+            let weight = (area / samples_per_triangle) as f64;
+
+            for i in 0..=n {
+                for j in 0..=n - i {
+                    let (u, v) = (i as f32 / n as f32, j as f32 / n as f32);
+                    let p = Point3::from_vec(
+                        a.to_vec() * (1.0 - u - v) + b.to_vec() * u + c.to_vec() * v,
+                    );
+                    let distance = other.closest_point(p)?.distance;
+                    max_distance = Some(max_distance.map_or(distance, |max| max.max(distance)));
+                    weighted_square_sum += weight * (distance as f64).powi(2);
+                    total_weight += weight;
+                }
+            }
+        }
+
+        let rms = if total_weight > 0.0 {
+            (weighted_square_sum / total_weight).sqrt() as f32
+        } else {
+            0.0
+        };
+        Some((max_distance?, rms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+
+    fn sphere(radius: f32) -> Mesh {
+        MeshBuilder::sphere(radius, 32, 16).build()
+    }
+
+    fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn mesh_matches_itself() {
+        let mesh = sphere(1.0);
+        let comparison = mesh.compare(&mesh, &ComparisonSettings::default()).unwrap();
+        assert_near(comparison.hausdorff_distance, 0.0, 1e-5);
+        assert_near(comparison.reference_hausdorff_distance, 0.0, 1e-5);
+        assert_near(comparison.rms_deviation, 0.0, 1e-5);
+        assert!(comparison.vertex_deviations.iter().all(|d| d.abs() <= 1e-5));
+    }
+
+    #[test]
+    fn concentric_spheres_are_their_radius_difference_apart() {
+        let (reference, larger) = (sphere(1.0), sphere(1.1));
+        let settings = ComparisonSettings::default();
+        let comparison = larger.compare(&reference, &settings).unwrap();
+        // Facets lie slightly inside the spheres, so allow for their sag.
+        let tolerance = 0.01;
+        assert_near(comparison.hausdorff_distance, 0.1, tolerance);
+        assert_near(comparison.reference_hausdorff_distance, 0.1, tolerance);
+        assert_near(comparison.symmetric_hausdorff_distance(), 0.1, tolerance);
+        assert_near(comparison.rms_deviation, 0.1, tolerance);
+
+        assert_near(
+            larger.hausdorff_distance(&reference, &settings).unwrap(),
+            comparison.hausdorff_distance,
+            0.0,
+        );
+        assert_near(
+            reference
+                .symmetric_hausdorff_distance(&larger, &settings)
+                .unwrap(),
+            comparison.symmetric_hausdorff_distance(),
+            0.0,
+        );
+    }
+
+    #[test]
+    fn deviations_are_signed() {
+        let reference = sphere(1.0);
+        for &(radius, expected) in &[(1.1, 0.1), (0.9, -0.1)] {
+            let deviations = sphere(radius).vertex_deviations(&reference).unwrap();
+            for deviation in deviations {
+                assert_near(deviation, expected, 0.01);
+            }
+        }
+
+        let mut mesh = sphere(1.1);
+        mesh.bake_deviation(&reference);
+        assert_eq!(
+            mesh.attributes.get(DEVIATION_ATTRIBUTE),
+            Some(&AttributeData::Float(
+                mesh.vertex_deviations(&reference).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn empty_meshes_cannot_be_compared() {
+        let (mesh, empty) = (sphere(1.0), Mesh::default());
+        let settings = ComparisonSettings::default();
+        assert_eq!(mesh.compare(&empty, &settings), None);
+        assert_eq!(empty.compare(&mesh, &settings), None);
+        assert_eq!(mesh.hausdorff_distance(&empty, &settings), None);
+        assert_eq!(empty.hausdorff_distance(&mesh, &settings), None);
+        assert_eq!(mesh.symmetric_hausdorff_distance(&empty, &settings), None);
+        assert_eq!(mesh.vertex_deviations(&empty), None);
+
+        let mut baked = sphere(1.0);
+        baked.bake_deviation(&empty);
+        assert!(baked.attributes.is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+use super::Mesh;
+use cgmath::Point3;
+use std::collections::HashMap;
+
+impl Mesh {
+    /// Splits the mesh into its connected components. Two triangles belong to
+    /// the same component if they share a vertex index or if any of their
+    /// vertices have identical positions, so meshes that store a separate copy
+    /// of each vertex for every triangle, such as meshes loaded from STL files,
+    /// are split correctly.
+    ///
+    /// Components are returned in the order in which their first triangle
+    /// appears in the mesh.
+    pub fn connected_components(&self) -> Vec<Mesh> {
+        self.component_triangle_indices()
+            .iter()
+            .map(|triangle_indices| self.submesh(triangle_indices))
+            .collect()
+    }
+
+    /// Returns the connected component that contains the most triangles, or
+    /// `None` if the mesh contains no triangles. This is useful for discarding
+    /// small disconnected pieces of noise from scanned meshes.
+    pub fn largest_component(&self) -> Option<Mesh> {
+        self.component_triangle_indices()
+            .iter()
+            .max_by_key(|triangle_indices| triangle_indices.len())
+            .map(|triangle_indices| self.submesh(triangle_indices))
+    }
+
+    /// Groups the indices of the mesh's triangles by connected component.
+    pub(crate) fn component_triangle_indices(&self) -> Vec<Vec<usize>> {
+        let vertex_roots = self.welded_vertex_roots();
+
+        // The first triangle that touches each vertex. Any later triangle that
+        // touches the same vertex is joined with it.
+        let mut first_triangle_at_vertex: Vec<Option<usize>> = vec![None; self.positions.len()];
+        let mut triangle_sets = DisjointSets::new(self.triangle_vertex_indices.len());
+
+        for (t, &(i1, i2, i3)) in self.triangle_vertex_indices.iter().enumerate() {
+            for &i in &[i1, i2, i3] {
+                let root = vertex_roots[i];
+                match first_triangle_at_vertex[root] {
+                    Some(other) => triangle_sets.union(t, other),
+                    None => first_triangle_at_vertex[root] = Some(t),
+                }
+            }
+        }
+
+        let mut component_at_root = HashMap::new();
+        let mut components: Vec<Vec<usize>> = vec![];
+        for t in 0..self.triangle_vertex_indices.len() {
+            let root = triangle_sets.find(t);
+            let component = *component_at_root.entry(root).or_insert_with(|| {
+                components.push(vec![]);
+                components.len() - 1
+            });
+            components[component].push(t);
+        }
+
+        components
+    }
+
+    /// Returns, for each vertex, the index of the first vertex in the mesh that
+    /// has exactly the same position.
+    pub(crate) fn welded_vertex_roots(&self) -> Vec<usize> {
+        let mut first_vertex_at_position = HashMap::new();
+        self.positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                *first_vertex_at_position
+                    .entry(position_key(*p))
+                    .or_insert(i)
+            })
+            .collect()
+    }
+
+    /// Creates a new mesh that contains only the given triangles of this mesh.
+    /// Vertices that are not referenced by any of the triangles are dropped.
+    pub(crate) fn submesh(&self, triangle_indices: &[usize]) -> Mesh {
+        let triangles: Vec<_> = triangle_indices
+            .iter()
+            .map(|&t| self.triangle_vertex_indices[t])
+            .collect();
+        self.with_triangles(triangle_indices, &triangles)
+    }
+
+    /// Creates a new mesh that contains the given triangles, which index into
+    /// this mesh's vertices. Each triangle takes the material of the triangle
+    /// at the corresponding index in `triangle_indices`. Vertices that are not
+    /// referenced by any of the triangles are dropped, and the others keep
+    /// their relative order of first use.
+    pub(crate) fn with_triangles(
+        &self,
+        triangle_indices: &[usize],
+        triangles: &[(usize, usize, usize)],
+    ) -> Mesh {
+        let mut new_index_of: HashMap<usize, usize> = HashMap::new();
+        let mut old_indices = vec![];
+        let mut triangle_vertex_indices = Vec::with_capacity(triangles.len());
+
+        let mut remap = |i: usize| {
+            *new_index_of.entry(i).or_insert_with(|| {
+                old_indices.push(i);
+                old_indices.len() - 1
+            })
+        };
+
+        for &(i1, i2, i3) in triangles {
+            triangle_vertex_indices.push((remap(i1), remap(i2), remap(i3)));
+        }
+
+        Mesh {
+            positions: old_indices.iter().map(|&i| self.positions[i]).collect(),
+            normals: old_indices.iter().map(|&i| self.normals[i]).collect(),
+            uvs: self
+                .uvs
+                .as_ref()
+                .map(|uvs| old_indices.iter().map(|&i| uvs[i]).collect()),
+            tangents: self
+                .tangents
+                .as_ref()
+                .map(|tangents| old_indices.iter().map(|&i| tangents[i]).collect()),
+            colors: self
+                .colors
+                .as_ref()
+                .map(|colors| old_indices.iter().map(|&i| colors[i]).collect()),
+            attributes: self
+                .attributes
+                .iter()
+                .map(|(name, data)| (name.clone(), data.select(&old_indices)))
+                .collect(),
+            triangle_vertex_indices,
+            materials: self.materials.clone(),
+            triangle_materials: self
+                .triangle_materials
+                .as_ref()
+                .map(|materials| triangle_indices.iter().map(|&t| materials[t]).collect()),
+        }
+    }
+}
+
+/// Returns a hashable key that is identical for two positions if and only if
+/// the positions are exactly equal.
+pub(crate) fn position_key(p: Point3<f32>) -> [u32; 3] {
+    // Adding zero turns negative zero into positive zero so that the two
+    // compare equal.
+    [
+        (p.x + 0.0).to_bits(),
+        (p.y + 0.0).to_bits(),
+        (p.z + 0.0).to_bits(),
+    ]
+}
+
+/// Asserts that every edge between welded vertices is used exactly once in each
+/// direction, so the mesh is closed and consistently wound.
+#[cfg(test)]
+pub(crate) fn assert_closed(mesh: &Mesh) {
+    let roots = mesh.welded_vertex_roots();
+    let mut edge_counts: HashMap<(usize, usize), usize> = HashMap::new();
+    for &(i1, i2, i3) in &mesh.triangle_vertex_indices {
+        for (a, b) in [(i1, i2), (i2, i3), (i3, i1)] {
+            *edge_counts.entry((roots[a], roots[b])).or_default() += 1;
+        }
+    }
+    for (&(a, b), &count) in &edge_counts {
+        assert_eq!(count, 1, "edge {:?} is used {} times", (a, b), count);
+        assert_eq!(
+            edge_counts.get(&(b, a)),
+            Some(&1),
+            "edge {:?} has no opposite",
+            (a, b)
+        );
+    }
+}
+
+/// A union-find structure over the integers `0..n`.
+pub(crate) struct DisjointSets {
+    parents: Vec<usize>,
+    ranks: Vec<u8>,
+}
+
+impl DisjointSets {
+    pub(crate) fn new(n: usize) -> Self {
+        Self {
+            parents: (0..n).collect(),
+            ranks: vec![0; n],
+        }
+    }
+
+    /// Returns the representative element of the set that contains `x`.
+    pub(crate) fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Compress the path so that later lookups are fast.
+        let mut x = x;
+        while self.parents[x] != root {
+            let next = self.parents[x];
+            self.parents[x] = root;
+            x = next;
+        }
+
+        root
+    }
+
+    /// Merges the sets that contain `a` and `b`.
+    pub(crate) fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return;
+        }
+
+        if self.ranks[a] < self.ranks[b] {
+            self.parents[a] = b;
+        } else if self.ranks[a] > self.ranks[b] {
+            self.parents[b] = a;
+        } else {
+            self.parents[b] = a;
+            self.ranks[a] += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Material, MeshBuilder};
+    use cgmath::{Vector3, Vector4};
+
+    /// Returns a copy of the mesh with a separate vertex for each corner of
+    /// each triangle, like a mesh loaded from an STL file.
+    fn unwelded(mesh: &Mesh) -> Mesh {
+        let corners: Vec<usize> = mesh
+            .triangle_vertex_indices
+            .iter()
+            .flat_map(|&(i1, i2, i3)| [i1, i2, i3])
+            .collect();
+        MeshBuilder::new(
+            corners.iter().map(|&i| mesh.positions[i]).collect(),
+            corners.iter().map(|&i| mesh.normals[i]).collect(),
+            (0..mesh.triangle_vertex_indices.len())
+                .map(|t| (3 * t, 3 * t + 1, 3 * t + 2))
+                .collect(),
+        )
+        .build()
+    }
+
+    #[test]
+    fn splits_disjoint_cuboids() {
+        let mut mesh = MeshBuilder::cuboid(Vector3::new(1.0, 1.0, 1.0), 1).build();
+        let mut other = MeshBuilder::cuboid(Vector3::new(1.0, 1.0, 1.0), 2).build();
+        other.translate(Vector3::new(3.0, 0.0, 0.0));
+        mesh.append(&other);
+
+        let components = mesh.connected_components();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].triangle_vertex_indices.len(), 12);
+        assert_eq!(components[1].triangle_vertex_indices.len(), 48);
+        for component in &components {
+            // Indices are remapped into the component's own vertices.
+            let vertex_count = component.positions.len();
+            assert!(component
+                .triangle_vertex_indices
+                .iter()
+                .all(|&(i1, i2, i3)| i1 < vertex_count && i2 < vertex_count && i3 < vertex_count));
+            assert_closed(component);
+        }
+        assert_eq!(components[0].positions.len(), 24);
+        assert!(components[1].positions.iter().all(|p| p.x > 2.0));
+
+        let largest = mesh.largest_component().unwrap();
+        assert_eq!(largest.positions, components[1].positions);
+    }
+
+    #[test]
+    fn welded_vertices_join_one_component() {
+        let mesh = unwelded(&MeshBuilder::sphere(1.0, 8, 4).build());
+        assert_eq!(mesh.connected_components().len(), 1);
+
+        let roots = mesh.welded_vertex_roots();
+        for (i, &root) in roots.iter().enumerate() {
+            assert!(root <= i);
+            assert_eq!(mesh.positions[root], mesh.positions[i]);
+            assert_eq!(roots[root], root);
+        }
+    }
+
+    #[test]
+    fn submesh_keeps_used_vertices_and_materials() {
+        let materials = vec![
+            Material::default(),
+            Material::new("red", Vector4::new(1.0, 0.0, 0.0, 1.0)),
+        ];
+        let mesh = MeshBuilder::plane(2.0, 1.0, 2, 1)
+            .materials(materials, vec![0, 0, 1, 1])
+            .build();
+
+        let submesh = mesh.submesh(&[3, 2]);
+        let (i1, i2, i3) = mesh.triangle_vertex_indices[3];
+        assert_eq!(submesh.triangle_vertex_indices[0], (0, 1, 2));
+        assert_eq!(
+            &submesh.positions[..3],
+            &[mesh.positions[i1], mesh.positions[i2], mesh.positions[i3]]
+        );
+        assert_eq!(submesh.positions.len(), 4);
+        assert_eq!(submesh.triangle_materials, Some(vec![1, 1]));
+        assert_eq!(submesh.materials, mesh.materials);
+        assert_eq!(submesh.uvs.as_ref().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn empty_mesh_has_no_components() {
+        let mesh = Mesh::default();
+        assert!(mesh.connected_components().is_empty());
+        assert!(mesh.largest_component().is_none());
+    }
+
+    #[test]
+    fn position_keys_treat_signed_zeros_as_equal() {
+        assert_eq!(
+            position_key(Point3::new(0.0, -0.0, 1.0)),
+            position_key(Point3::new(-0.0, 0.0, 1.0))
+        );
+    }
+}
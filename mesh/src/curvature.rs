@@ -0,0 +1,354 @@
+use super::Mesh;
+use cgmath::{InnerSpace, Vector3};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+/// Discrete curvature estimates at each vertex of a mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curvature {
+    /// The mean curvature at each vertex. This is positive where the surface
+    /// is convex, so it is `1 / r` on a sphere of radius `r` with outward
+    /// facing triangles.
+    pub mean: Vec<f32>,
+
+    /// The Gaussian curvature at each vertex. This is positive at elliptic
+    /// points, such as on a sphere, negative at saddle points, and zero where
+    /// the surface is flat or developable, such as on a cylinder.
+    pub gaussian: Vec<f32>,
+
+    /// The larger principal curvature at each vertex.
+    pub max_principal: Vec<f32>,
+
+    /// The smaller principal curvature at each vertex.
+    pub min_principal: Vec<f32>,
+
+    /// The unit direction in which the surface bends the most at each vertex,
+    /// which corresponds to `max_principal`. This is zero where the direction
+    /// could not be estimated.
+    pub max_direction: Vec<Vector3<f32>>,
+
+    /// The unit direction in which the surface bends the least at each vertex,
+    /// which corresponds to `min_principal`. This is perpendicular to
+    /// `max_direction` and to the surface normal.
+    pub min_direction: Vec<Vector3<f32>>,
+
+    /// Whether each vertex lies on the boundary of the mesh. Curvature
+    /// estimates are less reliable on the boundary, since only one side of the
+    /// surface is known.
+    pub is_boundary: Vec<bool>,
+}
+
+impl Mesh {
+    /// Estimates the curvature at each vertex.
+    ///
+    /// Mean curvature comes from the cotangent discretization of the
+    /// Laplace-Beltrami operator and Gaussian curvature from the angle deficit,
+    /// both normalized by the mixed Voronoi area around the vertex, as
+    /// described in Meyer et al.'s "Discrete Differential-Geometry Operators
+    /// for Triangulated 2-Manifolds". The principal curvatures follow from the
+    /// mean and Gaussian curvatures. The principal directions come from a
+    /// least-squares fit of the normal curvatures along the edges around each
+    /// vertex.
+    ///
+    /// On the boundary, the angle deficit is measured from `π` instead of
+    /// `2π`, so vertices along a straight edge of a flat patch have zero
+    /// Gaussian curvature. Only the normal component of the Laplacian is used,
+    /// so the mean curvature of a flat patch is zero on its boundary too.
+    ///
+    /// Vertices with identical positions are treated as one vertex, so meshes
+    /// loaded from STL files get smooth estimates, and vertices that are not
+    /// part of any triangle get zero curvature.
+    pub fn curvature(&self) -> Curvature {
+        let vertex_count = self.positions.len();
+        let roots = self.welded_vertex_roots();
+        let zero = Vector3::new(0.0, 0.0, 0.0);
+
+        let mut laplacians = vec![zero; vertex_count];
+        let mut areas = vec![0.0f32; vertex_count];
+        let mut angle_sums = vec![0.0f32; vertex_count];
+        let mut normals = vec![zero; vertex_count];
+        let mut edge_triangle_counts: HashMap<(usize, usize), usize> = HashMap::new();
+
+        for &(i1, i2, i3) in &self.triangle_vertex_indices {
+            let nodes = [roots[i1], roots[i2], roots[i3]];
+            if nodes[0] == nodes[1] || nodes[1] == nodes[2] || nodes[2] == nodes[0] {
+                continue;
+            }
+            let p = [
+                self.positions[nodes[0]],
+                self.positions[nodes[1]],
+                self.positions[nodes[2]],
+            ];
+            let area_normal = (p[1] - p[0]).cross(p[2] - p[0]);
+            let double_area = area_normal.magnitude();
+            if double_area == 0.0 {
+                continue;
+            }
+            let area = double_area / 2.0;
+
+            let mut cotangents = [0.0; 3];
+            let mut angles = [0.0; 3];
+            for k in 0..3 {
+                let u = p[(k + 1) % 3] - p[k];
+                let v = p[(k + 2) % 3] - p[k];
+                cotangents[k] = u.dot(v) / double_area;
+                angles[k] = u.angle(v).0;
+            }
+            let obtuse_corner = (0..3).find(|&k| angles[k] > PI / 2.0);
+
+            for k in 0..3 {
+                let (i, j, l) = (nodes[k], nodes[(k + 1) % 3], nodes[(k + 2) % 3]);
+                normals[i] += area_normal;
+                angle_sums[i] += angles[k];
+
+                // The edge from `i` to `j` is opposite the corner at `l`.
+                let weight = cotangents[(k + 2) % 3] / 2.0;
+                let edge = self.positions[j] - self.positions[i];
+                laplacians[i] -= edge * weight;
+                laplacians[j] += edge * weight;
+                *edge_triangle_counts
+                    .entry((i.min(j), i.max(j)))
+                    .or_insert(0) += 1;
+
+                areas[i] += match obtuse_corner {
+                    None => {
+                        let to_j = self.positions[j] - self.positions[i];
+                        let to_l = self.positions[l] - self.positions[i];
+                        (to_j.magnitude2() * cotangents[(k + 2) % 3]
+                            + to_l.magnitude2() * cotangents[(k + 1) % 3])
+                            / 8.0
+                    }
+                    Some(corner) if corner == k => area / 2.0,
+                    Some(_) => area / 4.0,
+                };
+            }
+        }
+
+        let mut is_boundary = vec![false; vertex_count];
+        let mut neighbors = vec![vec![]; vertex_count];
+        for (&(i, j), &count) in &edge_triangle_counts {
+            if count == 1 {
+                is_boundary[i] = true;
+                is_boundary[j] = true;
+            }
+            neighbors[i].push(j);
+            neighbors[j].push(i);
+        }
+
+        let mut curvature = Curvature {
+            mean: vec![0.0; vertex_count],
+            gaussian: vec![0.0; vertex_count],
+            max_principal: vec![0.0; vertex_count],
+            min_principal: vec![0.0; vertex_count],
+            max_direction: vec![zero; vertex_count],
+            min_direction: vec![zero; vertex_count],
+            is_boundary: vec![false; vertex_count],
+        };
+
+        for node in 0..vertex_count {
+            if roots[node] != node || areas[node] == 0.0 || normals[node].magnitude2() == 0.0 {
+                continue;
+            }
+            let normal = normals[node].normalize();
+
+            let mean = laplacians[node].dot(normal) / (2.0 * areas[node]);
+            let full_angle = if is_boundary[node] { PI } else { 2.0 * PI };
+            let gaussian = (full_angle - angle_sums[node]) / areas[node];
+            let discriminant = (mean * mean - gaussian).max(0.0).sqrt();
+
+            let (max_direction, min_direction) =
+                self.principal_directions(node, normal, &neighbors[node]);
+
+            curvature.mean[node] = mean;
+            curvature.gaussian[node] = gaussian;
+            curvature.max_principal[node] = mean + discriminant;
+            curvature.min_principal[node] = mean - discriminant;
+            curvature.max_direction[node] = max_direction;
+            curvature.min_direction[node] = min_direction;
+            curvature.is_boundary[node] = is_boundary[node];
+        }
+
+        // Copy each welded vertex's estimates to the vertices that share its
+        // position.
+        for (vertex, &node) in roots.iter().enumerate() {
+            if vertex != node {
+                curvature.mean[vertex] = curvature.mean[node];
+                curvature.gaussian[vertex] = curvature.gaussian[node];
+                curvature.max_principal[vertex] = curvature.max_principal[node];
+                curvature.min_principal[vertex] = curvature.min_principal[node];
+                curvature.max_direction[vertex] = curvature.max_direction[node];
+                curvature.min_direction[vertex] = curvature.min_direction[node];
+                curvature.is_boundary[vertex] = curvature.is_boundary[node];
+            }
+        }
+
+        curvature
+    }
+
+    /// Estimates the directions of maximum and minimum curvature at a vertex by
+    /// fitting a second fundamental form to the normal curvatures along the
+    /// edges to its neighbors. Returns zero vectors if there are too few
+    /// neighbors for a fit.
+    fn principal_directions(
+        &self,
+        vertex: usize,
+        normal: Vector3<f32>,
+        neighbors: &[usize],
+    ) -> (Vector3<f32>, Vector3<f32>) {
+        let zero = Vector3::new(0.0, 0.0, 0.0);
+
+        // Any two unit vectors that are perpendicular to each other and to the
+        // normal span the tangent plane.
+        let helper = if normal.x.abs() < 0.9 {
+            Vector3::new(1.0, 0.0, 0.0)
+        } else {
+            Vector3::new(0.0, 1.0, 0.0)
+        };
+        let e1 = normal.cross(helper).normalize();
+        let e2 = normal.cross(e1);
+
+        // Accumulate the normal equations for the symmetric form
+        // `[[a, b], [b, c]]`, with unknowns `(a, b, c)`.
+        let mut ata = [[0.0f32; 3]; 3];
+        let mut atb = [0.0f32; 3];
+        for &neighbor in neighbors {
+            let d = self.positions[neighbor] - self.positions[vertex];
+            let length2 = d.magnitude2();
+            let tangent = d - normal * d.dot(normal);
+            if length2 == 0.0 || tangent.magnitude2() == 0.0 {
+                continue;
+            }
+            let tangent = tangent.normalize();
+            // The surface curves away from the normal where it is convex.
+            let normal_curvature = -2.0 * d.dot(normal) / length2;
+            let (u, v) = (tangent.dot(e1), tangent.dot(e2));
+            let row = [u * u, 2.0 * u * v, v * v];
+            for r in 0..3 {
+                for c in 0..3 {
+                    ata[r][c] += row[r] * row[c];
+                }
+                atb[r] += row[r] * normal_curvature;
+            }
+        }
+
+        let (a, b, c) = match solve3(ata, atb) {
+            Some(solution) => solution,
+            None => return (zero, zero),
+        };
+
+        // The eigenvector of the larger eigenvalue of `[[a, b], [b, c]]` is at
+        // this angle from `e1`.
+        let angle = 0.5 * (2.0 * b).atan2(a - c);
+        let max_direction = e1 * angle.cos() + e2 * angle.sin();
+        let min_direction = normal.cross(max_direction);
+        (max_direction, min_direction)
+    }
+}
+
+/// Solves the 3x3 linear system `m * x = r` with Cramer's rule, or returns
+/// `None` if `m` is singular.
+fn solve3(m: [[f32; 3]; 3], r: [f32; 3]) -> Option<(f32, f32, f32)> {
+    let det3 = |m: [[f32; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let det = det3(m);
+    let scale = m.iter().flatten().fold(0.0f32, |acc, v| acc.max(v.abs()));
+    if det.abs() <= 1e-6 * scale * scale * scale {
+        return None;
+    }
+
+    let with_column = |column: usize| {
+        let mut m = m;
+        for row in 0..3 {
+            m[row][column] = r[row];
+        }
+        det3(m) / det
+    };
+    Some((with_column(0), with_column(1), with_column(2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+
+    fn assert_near(actual: f32, expected: f32, tolerance: f32, what: &str) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} is {}, expected {}",
+            what,
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn sphere_has_constant_curvature() {
+        let radius = 2.0;
+        let mesh = MeshBuilder::sphere(radius, 48, 24).build();
+        let curvature = mesh.curvature();
+        for (i, p) in mesh.positions.iter().enumerate() {
+            // The poles have many thin triangles around them, so their
+            // estimates are less accurate.
+            if p.z.abs() > 0.99 * radius {
+                continue;
+            }
+            assert!(!curvature.is_boundary[i]);
+            assert_near(curvature.mean[i], 1.0 / radius, 0.02, "mean curvature");
+            assert_near(
+                curvature.gaussian[i],
+                1.0 / (radius * radius),
+                0.02,
+                "Gaussian curvature",
+            );
+            assert_near(
+                curvature.max_principal[i],
+                1.0 / radius,
+                0.05,
+                "max principal curvature",
+            );
+            assert_near(
+                curvature.min_principal[i],
+                1.0 / radius,
+                0.05,
+                "min principal curvature",
+            );
+        }
+    }
+
+    #[test]
+    fn plane_is_flat() {
+        let mesh = MeshBuilder::plane(2.0, 1.0, 8, 4).build();
+        let curvature = mesh.curvature();
+        for (i, p) in mesh.positions.iter().enumerate() {
+            assert_near(curvature.mean[i], 0.0, 1e-4, "mean curvature");
+            // The boundary turns at the corners, which shows up as Gaussian
+            // curvature.
+            if p.x.abs() == 1.0 && p.y.abs() == 0.5 {
+                assert!(curvature.gaussian[i] > 0.0);
+                continue;
+            }
+            assert_near(curvature.gaussian[i], 0.0, 1e-4, "Gaussian curvature");
+            assert_near(curvature.max_principal[i], 0.0, 1e-3, "max principal");
+            assert_near(curvature.min_principal[i], 0.0, 1e-3, "min principal");
+        }
+        // Corners and edges of the grid are on the boundary.
+        assert_eq!(curvature.is_boundary.iter().filter(|&&b| b).count(), 24);
+    }
+
+    #[test]
+    fn principal_directions_are_tangent_on_a_torus() {
+        let mesh = MeshBuilder::torus(2.0, 0.5, 48, 24).build();
+        let curvature = mesh.curvature();
+        for (i, n) in mesh.normals.iter().enumerate() {
+            let (max, min) = (curvature.max_direction[i], curvature.min_direction[i]);
+            assert_near(max.dot(*n), 0.0, 0.05, "max direction along normal");
+            assert_near(min.dot(*n), 0.0, 0.05, "min direction along normal");
+            assert_near(max.dot(min), 0.0, 1e-3, "max direction along min");
+            // The tube bends the most, with a curvature of 1 / 0.5.
+            assert_near(curvature.max_principal[i], 2.0, 0.1, "max principal");
+        }
+    }
+}
@@ -0,0 +1,464 @@
+use super::bvh::Aabb;
+use super::components::position_key;
+use super::Mesh;
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Rad, Vector3};
+use std::collections::HashMap;
+
+/// Settings for [`Mesh::find_defects`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DefectSettings {
+    /// A triangle is a needle if its shortest edge is shorter than this
+    /// fraction of its longest edge.
+    pub min_edge_ratio: f32,
+
+    /// A triangle is a sliver if its largest angle is at least this large.
+    pub max_angle: Rad<f32>,
+}
+
+impl Default for DefectSettings {
+    fn default() -> Self {
+        DefectSettings {
+            min_edge_ratio: 0.01,
+            max_angle: Deg(179.0).into(),
+        }
+    }
+}
+
+/// The results of [`Mesh::find_defects`]. Each vector contains the indices of
+/// the offending triangles in ascending order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefectReport {
+    /// Triangles that pass through another triangle of the mesh, including
+    /// neighbors that are folded flat onto each other.
+    pub self_intersecting: Vec<usize>,
+
+    /// Triangles that are nearly flattened into a line, with one angle close
+    /// to 180 degrees.
+    pub slivers: Vec<usize>,
+
+    /// Triangles with one edge that is much shorter than the others, including
+    /// triangles with two vertices at the same position.
+    pub needles: Vec<usize>,
+
+    /// Triangles whose three corners are at the same positions as those of
+    /// another triangle, regardless of order or winding.
+    pub duplicates: Vec<usize>,
+}
+
+impl DefectReport {
+    /// Returns true if no defects were found.
+    pub fn is_empty(&self) -> bool {
+        self.self_intersecting.is_empty()
+            && self.slivers.is_empty()
+            && self.needles.is_empty()
+            && self.duplicates.is_empty()
+    }
+
+    /// Returns the indices of every triangle that has any defect, in ascending
+    /// order and without repeats.
+    pub fn defective_triangles(&self) -> Vec<usize> {
+        let mut triangles: Vec<usize> = self
+            .self_intersecting
+            .iter()
+            .chain(&self.slivers)
+            .chain(&self.needles)
+            .chain(&self.duplicates)
+            .copied()
+            .collect();
+        triangles.sort_unstable();
+        triangles.dedup();
+        triangles
+    }
+}
+
+impl Mesh {
+    /// Finds triangles that intersect the rest of the mesh or are badly
+    /// shaped. These cause z-fighting when rendered and often make meshes
+    /// impossible to slice for printing.
+    pub fn find_defects(&self, settings: &DefectSettings) -> DefectReport {
+        DefectReport {
+            self_intersecting: self.self_intersecting_triangles(),
+            slivers: self.sliver_triangles(settings.max_angle),
+            needles: self.needle_triangles(settings.min_edge_ratio),
+            duplicates: self.duplicate_triangles(),
+        }
+    }
+
+    /// Returns the indices of the triangles that pass through another triangle
+    /// of the mesh, in ascending order. Candidate pairs are found with a
+    /// bounding volume hierarchy.
+    ///
+    /// Triangles that only touch along their edges or at their corners do not
+    /// count, and neither do triangles whose corner rests on another triangle
+    /// without passing through it. Vertices with identical positions are
+    /// treated as one vertex, so neighboring triangles never intersect each
+    /// other unless they are folded flat onto each other, with both triangles
+    /// on the same side of their shared edge. Duplicate and degenerate
+    /// triangles are reported by the other checks instead.
+    pub fn self_intersecting_triangles(&self) -> Vec<usize> {
+        let roots = self.welded_vertex_roots();
+        let bvh = self.bvh();
+        let mut is_intersecting = vec![false; self.triangle_vertex_indices.len()];
+
+        for (t, &(i1, i2, i3)) in self.triangle_vertex_indices.iter().enumerate() {
+            let a = [roots[i1], roots[i2], roots[i3]];
+            let bounds = Aabb::from_points(&[
+                self.positions[a[0]],
+                self.positions[a[1]],
+                self.positions[a[2]],
+            ]);
+            for other in bvh.overlapping_triangles(&bounds) {
+                if other <= t {
+                    continue;
+                }
+                let (j1, j2, j3) = self.triangle_vertex_indices[other];
+                let b = [roots[j1], roots[j2], roots[j3]];
+                if self.triangles_intersect(a, b) {
+                    is_intersecting[t] = true;
+                    is_intersecting[other] = true;
+                }
+            }
+        }
+
+        (0..is_intersecting.len())
+            .filter(|&t| is_intersecting[t])
+            .collect()
+    }
+
+    /// Returns the indices of the triangles whose largest angle is at least
+    /// `max_angle`, in ascending order. Triangles with coincident vertices are
+    /// needles rather than slivers.
+    pub fn sliver_triangles<A: Into<Rad<f32>>>(&self, max_angle: A) -> Vec<usize> {
+        let max_angle = max_angle.into();
+        self.triangles_iter()
+            .filter(|triangle| {
+                let (p1, p2, p3) = triangle.positions();
+                let p = [p1, p2, p3];
+                (0..3).any(|k| {
+                    let u = p[(k + 1) % 3] - p[k];
+                    let v = p[(k + 2) % 3] - p[k];
+                    u.magnitude2() > 0.0 && v.magnitude2() > 0.0 && u.angle(v) >= max_angle
+                })
+            })
+            .map(|triangle| triangle.index())
+            .collect()
+    }
+
+    /// Returns the indices of the triangles whose shortest edge is shorter
+    /// than `min_edge_ratio` times their longest edge, in ascending order.
+    /// Triangles with two vertices at the same position are always needles,
+    /// and triangles with all three are skipped.
+    pub fn needle_triangles(&self, min_edge_ratio: f32) -> Vec<usize> {
+        self.triangles_iter()
+            .filter(|triangle| {
+                let (p1, p2, p3) = triangle.positions();
+                let lengths = [
+                    (p2 - p1).magnitude(),
+                    (p3 - p2).magnitude(),
+                    (p1 - p3).magnitude(),
+                ];
+                let shortest = lengths[0].min(lengths[1]).min(lengths[2]);
+                let longest = lengths[0].max(lengths[1]).max(lengths[2]);
+                longest > 0.0 && shortest < min_edge_ratio * longest
+            })
+            .map(|triangle| triangle.index())
+            .collect()
+    }
+
+    /// Returns the indices of the triangles whose corners are at the same three
+    /// positions as those of another triangle, regardless of order or
+    /// winding, in ascending order. Every triangle in a group of duplicates is
+    /// included.
+    pub fn duplicate_triangles(&self) -> Vec<usize> {
+        let mut triangles_at: HashMap<[[u32; 3]; 3], Vec<usize>> = HashMap::new();
+        for (t, &(i1, i2, i3)) in self.triangle_vertex_indices.iter().enumerate() {
+            let mut key = [
+                position_key(self.positions[i1]),
+                position_key(self.positions[i2]),
+                position_key(self.positions[i3]),
+            ];
+            key.sort_unstable();
+            triangles_at.entry(key).or_default().push(t);
+        }
+
+        let mut duplicates: Vec<usize> = triangles_at
+            .into_values()
+            .filter(|triangles| triangles.len() > 1)
+            .flatten()
+            .collect();
+        duplicates.sort_unstable();
+        duplicates
+    }
+
+    /// Returns true if the triangles with the given vertices cross each other
+    /// at any point that is not a vertex they share. Triangles that share an
+    /// edge only intersect if they are folded flat onto each other. Triangles
+    /// that share all three vertices, or that are degenerate, never intersect.
+    fn triangles_intersect(&self, a: [usize; 3], b: [usize; 3]) -> bool {
+        let shared: Vec<usize> = a.iter().copied().filter(|i| b.contains(i)).collect();
+        if shared.len() == 3 {
+            return false;
+        }
+
+        let to_f64 = |i: usize| self.positions[i].to_vec().cast::<f64>().unwrap();
+        let pa = [to_f64(a[0]), to_f64(a[1]), to_f64(a[2])];
+        let pb = [to_f64(b[0]), to_f64(b[1]), to_f64(b[2])];
+        let na = (pa[1] - pa[0]).cross(pa[2] - pa[0]);
+        let nb = (pb[1] - pb[0]).cross(pb[2] - pb[0]);
+        if na.magnitude2() == 0.0 || nb.magnitude2() == 0.0 {
+            return false;
+        }
+        let (na, nb) = (na.normalize(), nb.normalize());
+
+        let longest_edge = pa
+            .iter()
+            .chain(&pb)
+            .flat_map(|p| pa.iter().chain(&pb).map(move |q| (p - q).magnitude()))
+            .fold(0.0f64, f64::max);
+        let tolerance = longest_edge * 1e-6;
+
+        // Triangles that share an edge overlap only if they are in the same
+        // plane with their third vertices on the same side of the edge.
+        if shared.len() == 2 {
+            let third = |v: &[usize; 3], p: &[Vector3<f64>; 3]| {
+                p[(0..3).find(|&k| !shared.contains(&v[k])).unwrap()]
+            };
+            let (ta, tb) = (third(&a, &pa), third(&b, &pb));
+            let (s0, s1) = (to_f64(shared[0]), to_f64(shared[1]));
+            let edge = s1 - s0;
+            let is_coplanar = na.dot(tb - s0).abs() <= tolerance;
+            return is_coplanar && edge.cross(ta - s0).dot(edge.cross(tb - s0)) > 0.0;
+        }
+
+        // The signed distances of each triangle's vertices from the other's
+        // plane. A triangle that is entirely on one side can't intersect.
+        let da = pa.map(|p| nb.dot(p - pb[0]));
+        let db = pb.map(|p| na.dot(p - pa[0]));
+        let all_positive = |d: [f64; 3]| d.iter().all(|&d| d > 0.0);
+        let all_negative = |d: [f64; 3]| d.iter().all(|&d| d < 0.0);
+        if all_positive(da) || all_negative(da) || all_positive(db) || all_negative(db) {
+            return false;
+        }
+
+        if da.iter().chain(&db).all(|d| d.abs() <= tolerance) {
+            return coplanar_triangles_intersect(pa, pb, na, &a, &b);
+        }
+
+        // Otherwise, the triangles intersect along a segment whose endpoints
+        // are where edges of one triangle pierce the other. Only the edges
+        // opposite a shared vertex can pierce the other triangle anywhere but
+        // at that vertex.
+        let edges = |v: &[usize; 3]| {
+            (0..3)
+                .map(|k| (k, (k + 1) % 3))
+                .filter(|&(k, l)| !shared.contains(&v[k]) && !shared.contains(&v[l]))
+                .collect::<Vec<_>>()
+        };
+        edges(&a)
+            .into_iter()
+            .any(|(k, l)| segment_pierces_triangle(pa[k], pa[l], da[k], da[l], pb, nb))
+            || edges(&b)
+                .into_iter()
+                .any(|(k, l)| segment_pierces_triangle(pb[k], pb[l], db[k], db[l], pa, na))
+    }
+}
+
+/// Returns true if the segment from `p` to `q`, whose signed distances from
+/// the triangle's plane are `dp` and `dq`, passes through the triangle's
+/// interior.
+fn segment_pierces_triangle(
+    p: Vector3<f64>,
+    q: Vector3<f64>,
+    dp: f64,
+    dq: f64,
+    triangle: [Vector3<f64>; 3],
+    normal: Vector3<f64>,
+) -> bool {
+    if dp * dq >= 0.0 {
+        return false;
+    }
+    let x = p + (q - p) * (dp / (dp - dq));
+    (0..3).all(|k| {
+        let edge = triangle[(k + 1) % 3] - triangle[k];
+        edge.cross(x - triangle[k]).dot(normal) > 0.0
+    })
+}
+
+/// Returns true if two triangles in the same plane overlap anywhere but at a
+/// shared vertex: if an edge of one properly crosses an edge of the other, or
+/// if a vertex of one that isn't shared is strictly inside the other.
+fn coplanar_triangles_intersect(
+    pa: [Vector3<f64>; 3],
+    pb: [Vector3<f64>; 3],
+    normal: Vector3<f64>,
+    a: &[usize; 3],
+    b: &[usize; 3],
+) -> bool {
+    // Project onto the coordinate plane in which the triangles are largest.
+    let axis = (0..3)
+        .max_by(|&i, &j| normal[i].abs().total_cmp(&normal[j].abs()))
+        .unwrap_or(2);
+    let (x, y) = ((axis + 1) % 3, (axis + 2) % 3);
+    let project = |p: Vector3<f64>| (p[x], p[y]);
+    let qa = pa.map(project);
+    let qb = pb.map(project);
+
+    let orient = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    };
+    let strictly_inside = |p: (f64, f64), t: [(f64, f64); 3]| {
+        let o = [
+            orient(t[0], t[1], p),
+            orient(t[1], t[2], p),
+            orient(t[2], t[0], p),
+        ];
+        o.iter().all(|&o| o > 0.0) || o.iter().all(|&o| o < 0.0)
+    };
+
+    for k in 0..3 {
+        for l in 0..3 {
+            let (p, q) = (qa[k], qa[(k + 1) % 3]);
+            let (r, s) = (qb[l], qb[(l + 1) % 3]);
+            if orient(p, q, r) * orient(p, q, s) < 0.0 && orient(r, s, p) * orient(r, s, q) < 0.0 {
+                return true;
+            }
+        }
+    }
+
+    (0..3).any(|k| !b.contains(&a[k]) && strictly_inside(qa[k], qb))
+        || (0..3).any(|k| !a.contains(&b[k]) && strictly_inside(qb[k], qa))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+    use cgmath::Point3;
+
+    /// Creates a mesh from triangles given by the positions of their corners.
+    /// Corners at the same position are separate vertices.
+    fn mesh(triangles: &[[[f32; 3]; 3]]) -> Mesh {
+        let positions: Vec<Point3<f32>> = triangles
+            .iter()
+            .flatten()
+            .map(|&[x, y, z]| Point3::new(x, y, z))
+            .collect();
+        let normals = vec![Vector3::new(0.0, 0.0, 1.0); positions.len()];
+        let triangle_vertex_indices = (0..triangles.len())
+            .map(|t| (3 * t, 3 * t + 1, 3 * t + 2))
+            .collect();
+        MeshBuilder::new(positions, normals, triangle_vertex_indices).build()
+    }
+
+    #[test]
+    fn crossing_quads_intersect() {
+        let mut mesh = MeshBuilder::plane(2.0, 2.0, 1, 1).build();
+        let mut crossing = MeshBuilder::plane(2.0, 2.0, 1, 1).build();
+        crossing.rotate(Vector3::new(1.0, 0.0, 0.0), Deg(90.0));
+        mesh.append(&crossing);
+        assert_eq!(mesh.self_intersecting_triangles(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn neighbors_do_not_intersect() {
+        let sphere = MeshBuilder::sphere(1.0, 16, 8).build();
+        assert_eq!(sphere.self_intersecting_triangles(), vec![]);
+
+        // Separate vertices at the same positions are welded.
+        let sharing_edge = mesh(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, -1.0, 0.5]],
+        ]);
+        assert_eq!(sharing_edge.self_intersecting_triangles(), vec![]);
+
+        let sharing_vertex = mesh(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[0.0, 0.0, 0.0], [-1.0, 0.0, 1.0], [0.0, -1.0, -1.0]],
+        ]);
+        assert_eq!(sharing_vertex.self_intersecting_triangles(), vec![]);
+    }
+
+    #[test]
+    fn neighbor_through_shared_vertex_intersects() {
+        // The second triangle shares a corner with the first and passes
+        // through its interior.
+        let mesh = mesh(&[
+            [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0]],
+            [[0.0, 0.0, 0.0], [1.0, 1.0, -1.0], [1.0, 0.5, 1.0]],
+        ]);
+        assert_eq!(mesh.self_intersecting_triangles(), vec![0, 1]);
+    }
+
+    #[test]
+    fn coplanar_triangles_intersect_if_they_overlap() {
+        let overlapping = mesh(&[
+            [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0]],
+            [[0.5, 0.5, 0.0], [3.0, 0.5, 0.0], [0.5, 3.0, 0.0]],
+        ]);
+        assert_eq!(overlapping.self_intersecting_triangles(), vec![0, 1]);
+
+        let apart = mesh(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[2.0, 0.0, 0.0], [3.0, 0.0, 0.0], [2.0, 1.0, 0.0]],
+        ]);
+        assert_eq!(apart.self_intersecting_triangles(), vec![]);
+    }
+
+    #[test]
+    fn folded_neighbors_intersect() {
+        let folded = mesh(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+        ]);
+        assert_eq!(folded.self_intersecting_triangles(), vec![0, 1]);
+
+        // Neighbors in the same plane on opposite sides of the edge are fine.
+        let flat = mesh(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, -1.0, 0.0]],
+        ]);
+        assert_eq!(flat.self_intersecting_triangles(), vec![]);
+    }
+
+    #[test]
+    fn touching_vertex_does_not_intersect() {
+        // A corner rests on the interior of the first triangle.
+        let touching_inside = mesh(&[
+            [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0]],
+            [[0.5, 0.5, 0.0], [1.0, 0.5, 1.0], [0.5, 1.0, 1.0]],
+        ]);
+        assert_eq!(touching_inside.self_intersecting_triangles(), vec![]);
+
+        // A corner is in the first triangle's plane but outside of it.
+        let touching_plane = mesh(&[
+            [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0]],
+            [[3.0, 3.0, 0.0], [2.0, 1.5, 1.0], [1.5, 2.0, -1.0]],
+        ]);
+        assert_eq!(touching_plane.self_intersecting_triangles(), vec![]);
+    }
+
+    #[test]
+    fn duplicates_ignore_winding() {
+        let mesh = mesh(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[2.0, 0.0, 0.0], [3.0, 0.0, 0.0], [2.0, 1.0, 0.0]],
+            [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 0.0]],
+        ]);
+        assert_eq!(mesh.duplicate_triangles(), vec![0, 2]);
+        // Duplicates are not reported as intersecting.
+        assert_eq!(mesh.self_intersecting_triangles(), vec![]);
+    }
+
+    #[test]
+    fn finds_needles_and_slivers() {
+        let mesh = mesh(&[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[0.0, 2.0, 0.0], [1.0, 2.0, 0.0], [0.0, 2.001, 0.0]],
+            [[0.0, 4.0, 0.0], [2.0, 4.0, 0.0], [1.0, 4.001, 0.0]],
+        ]);
+        let report = mesh.find_defects(&DefectSettings::default());
+        assert_eq!(report.needles, vec![1]);
+        assert_eq!(report.slivers, vec![2]);
+        assert_eq!(report.defective_triangles(), vec![1, 2]);
+    }
+}
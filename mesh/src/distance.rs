@@ -0,0 +1,414 @@
+use super::bvh::{Bvh, BvhNodeKind};
+use super::Mesh;
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3};
+use std::collections::HashMap;
+
+/// The point on a mesh's surface that is closest to a query point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClosestPoint {
+    /// The index of the triangle that contains the closest point.
+    pub triangle: usize,
+
+    /// The closest point.
+    pub point: Point3<f32>,
+
+    /// The barycentric coordinates of the closest point with respect to the
+    /// triangle's first, second, and third vertices.
+    pub barycentric: Vector3<f32>,
+
+    /// The distance from the query point to the closest point.
+    pub distance: f32,
+}
+
+impl<'msh> Bvh<'msh> {
+    /// Returns the point on the mesh's surface that is closest to `p`, or
+    /// `None` if the mesh has no triangles.
+    pub fn closest_point(&self, p: Point3<f32>) -> Option<ClosestPoint> {
+        self.closest_point_with_feature(p)
+            .map(|(closest, _)| closest)
+    }
+
+    fn closest_point_with_feature(&self, p: Point3<f32>) -> Option<(ClosestPoint, Feature)> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut best: Option<(ClosestPoint, Feature)> = None;
+        let mut best_distance2 = f32::INFINITY;
+        let mut stack = vec![0];
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.bounds.distance2(p) >= best_distance2 {
+                continue;
+            }
+
+            match node.kind {
+                BvhNodeKind::Leaf { start, count } => {
+                    for &t in &self.triangle_indices[start..start + count] {
+                        let (a, b, c) = self.mesh.triangle_at(t).positions();
+                        let (barycentric, feature) = closest_point_on_triangle(p, a, b, c);
+                        let point = Point3::from_vec(
+                            a.to_vec() * barycentric.x
+                                + b.to_vec() * barycentric.y
+                                + c.to_vec() * barycentric.z,
+                        );
+                        let distance2 = (point - p).magnitude2();
+                        if distance2 < best_distance2 {
+                            best_distance2 = distance2;
+                            best = Some((
+                                ClosestPoint {
+                                    triangle: t,
+                                    point,
+                                    barycentric,
+                                    distance: distance2.sqrt(),
+                                },
+                                feature,
+                            ));
+                        }
+                    }
+                }
+                BvhNodeKind::Interior { right } => {
+                    // Push the farther child first so that the nearer child is
+                    // searched first and tightens the bound sooner.
+                    let left = node_index + 1;
+                    let left_distance2 = self.nodes[left].bounds.distance2(p);
+                    let right_distance2 = self.nodes[right].bounds.distance2(p);
+                    if left_distance2 < right_distance2 {
+                        stack.push(right);
+                        stack.push(left);
+                    } else {
+                        stack.push(left);
+                        stack.push(right);
+                    }
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// Answers signed distance queries against a mesh. Points inside the mesh have
+/// a negative distance, and points outside have a positive distance.
+///
+/// The sign is determined by the angle-weighted pseudo-normal of the closest
+/// feature on the surface, which is exact for closed, consistently oriented
+/// meshes. Vertices at the same position are treated as one vertex, so meshes
+/// that store separate vertices for each triangle, such as meshes loaded from
+/// STL files, are handled correctly.
+#[derive(Debug, Clone)]
+pub struct SignedDistanceField<'msh> {
+    bvh: Bvh<'msh>,
+
+    /// For each vertex, the index of the first vertex at the same position.
+    vertex_roots: Vec<usize>,
+
+    /// Angle-weighted pseudo-normals, indexed by root vertex.
+    vertex_normals: Vec<Vector3<f32>>,
+
+    /// Pseudo-normals of edges, keyed by the edge's root vertices in ascending
+    /// order.
+    edge_normals: HashMap<(usize, usize), Vector3<f32>>,
+}
+
+impl<'msh> SignedDistanceField<'msh> {
+    pub fn new(mesh: &'msh Mesh) -> Self {
+        let vertex_roots = mesh.welded_vertex_roots();
+        let mut vertex_normals = vec![Vector3::new(0.0, 0.0, 0.0); mesh.positions.len()];
+        let mut edge_normals = HashMap::new();
+
+        for t in mesh.triangles_iter() {
+            let normal = t.face_normal();
+            let (i1, i2, i3) = t.vertex_indices();
+            let corners = [i1, i2, i3];
+
+            for k in 0..3 {
+                let i = corners[k];
+                let j = corners[(k + 1) % 3];
+                let l = corners[(k + 2) % 3];
+
+                let p = mesh.positions[i];
+                let angle = (mesh.positions[j] - p).angle(mesh.positions[l] - p).0;
+                if angle.is_finite() {
+                    vertex_normals[vertex_roots[i]] += normal * angle;
+                }
+
+                *edge_normals
+                    .entry(edge_key(vertex_roots[i], vertex_roots[j]))
+                    .or_insert_with(|| Vector3::new(0.0, 0.0, 0.0)) += normal;
+            }
+        }
+
+        SignedDistanceField {
+            bvh: Bvh::new(mesh),
+            vertex_roots,
+            vertex_normals,
+            edge_normals,
+        }
+    }
+
+    /// Returns the point on the mesh's surface that is closest to `p`, or
+    /// `None` if the mesh has no triangles.
+    pub fn closest_point(&self, p: Point3<f32>) -> Option<ClosestPoint> {
+        self.bvh.closest_point(p)
+    }
+
+    /// Returns the signed distance from `p` to the mesh's surface, or `None`
+    /// if the mesh has no triangles.
+    pub fn signed_distance(&self, p: Point3<f32>) -> Option<f32> {
+        let (closest, feature) = self.bvh.closest_point_with_feature(p)?;
+        let (i1, i2, i3) = self.bvh.mesh.triangle_vertex_indices[closest.triangle];
+        let roots = [
+            self.vertex_roots[i1],
+            self.vertex_roots[i2],
+            self.vertex_roots[i3],
+        ];
+
+        let pseudo_normal = match feature {
+            Feature::Face => self.bvh.mesh.triangle_at(closest.triangle).face_normal(),
+            Feature::Edge(k) => self.edge_normals[&edge_key(roots[k], roots[(k + 1) % 3])],
+            Feature::Vertex(k) => self.vertex_normals[roots[k]],
+        };
+
+        if (p - closest.point).dot(pseudo_normal) < 0.0 {
+            Some(-closest.distance)
+        } else {
+            Some(closest.distance)
+        }
+    }
+}
+
+impl Mesh {
+    /// Builds a structure for answering signed distance queries against the
+    /// mesh.
+    pub fn signed_distance_field(&self) -> SignedDistanceField<'_> {
+        SignedDistanceField::new(self)
+    }
+
+    /// Returns the point on the mesh's surface that is closest to `p`, or
+    /// `None` if the mesh has no triangles.
+    ///
+    /// This builds a bounding volume hierarchy for every call. Use
+    /// [`Mesh::bvh`] to make many queries against the same mesh.
+    pub fn closest_point(&self, p: Point3<f32>) -> Option<ClosestPoint> {
+        self.bvh().closest_point(p)
+    }
+
+    /// Returns the signed distance from `p` to the mesh's surface, or `None`
+    /// if the mesh has no triangles. See [`SignedDistanceField`] for how the
+    /// sign is determined.
+    ///
+    /// This builds a new signed distance field for every call. Use
+    /// [`Mesh::signed_distance_field`] to make many queries against the same
+    /// mesh.
+    pub fn signed_distance(&self, p: Point3<f32>) -> Option<f32> {
+        self.signed_distance_field().signed_distance(p)
+    }
+}
+
+/// The feature of a triangle on which a closest point lies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Feature {
+    Face,
+
+    /// The edge from the triangle's `k`th vertex to its `(k + 1) % 3`th vertex.
+    Edge(usize),
+
+    /// The triangle's `k`th vertex.
+    Vertex(usize),
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Returns the barycentric coordinates of the point on triangle `abc` that is
+/// closest to `p`, along with the feature of the triangle on which it lies.
+///
+/// This follows the Voronoi region approach from Christer Ericson's *Real-Time
+/// Collision Detection*, section 5.1.5.
+fn closest_point_on_triangle(
+    p: Point3<f32>,
+    a: Point3<f32>,
+    b: Point3<f32>,
+    c: Point3<f32>,
+) -> (Vector3<f32>, Feature) {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return (Vector3::new(1.0, 0.0, 0.0), Feature::Vertex(0));
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return (Vector3::new(0.0, 1.0, 0.0), Feature::Vertex(1));
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = d1 / (d1 - d3);
+        return (Vector3::new(1.0 - v, v, 0.0), Feature::Edge(0));
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return (Vector3::new(0.0, 0.0, 1.0), Feature::Vertex(2));
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = d2 / (d2 - d6);
+        return (Vector3::new(1.0 - w, 0.0, w), Feature::Edge(2));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (Vector3::new(0.0, 1.0 - w, w), Feature::Edge(1));
+    }
+
+    let denom = va + vb + vc;
+    if denom == 0.0 {
+        // The triangle is degenerate, so fall back to its first vertex.
+        return (Vector3::new(1.0, 0.0, 0.0), Feature::Vertex(0));
+    }
+    let v = vb / denom;
+    let w = vc / denom;
+    (Vector3::new(1.0 - v - w, v, w), Feature::Face)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn closest_point_on_triangle_finds_each_voronoi_region() {
+        let a = Point3::new(0.0, 0.0, 0.0);
+        let b = Point3::new(1.0, 0.0, 0.0);
+        let c = Point3::new(0.0, 1.0, 0.0);
+        let cases = [
+            (
+                Point3::new(-1.0, -1.0, 1.0),
+                Feature::Vertex(0),
+                Vector3::new(1.0, 0.0, 0.0),
+            ),
+            (
+                Point3::new(2.0, -0.5, 1.0),
+                Feature::Vertex(1),
+                Vector3::new(0.0, 1.0, 0.0),
+            ),
+            (
+                Point3::new(-0.5, 2.0, -1.0),
+                Feature::Vertex(2),
+                Vector3::new(0.0, 0.0, 1.0),
+            ),
+            (
+                Point3::new(0.25, -1.0, 1.0),
+                Feature::Edge(0),
+                Vector3::new(0.75, 0.25, 0.0),
+            ),
+            (
+                Point3::new(1.0, 1.0, 1.0),
+                Feature::Edge(1),
+                Vector3::new(0.0, 0.5, 0.5),
+            ),
+            (
+                Point3::new(-1.0, 0.25, -1.0),
+                Feature::Edge(2),
+                Vector3::new(0.75, 0.0, 0.25),
+            ),
+            (
+                Point3::new(0.25, 0.25, -2.0),
+                Feature::Face,
+                Vector3::new(0.5, 0.25, 0.25),
+            ),
+        ];
+        for &(p, feature, barycentric) in &cases {
+            let (actual_barycentric, actual_feature) = closest_point_on_triangle(p, a, b, c);
+            assert_eq!(actual_feature, feature, "query point {:?}", p);
+            assert!(
+                (actual_barycentric - barycentric).magnitude() < 1e-6,
+                "query point {:?} gave {:?}",
+                p,
+                actual_barycentric
+            );
+        }
+    }
+
+    #[test]
+    fn signed_distance_to_cuboid_has_expected_sign_and_magnitude() {
+        // Each face has its own vertices, so edges and corners are only shared
+        // through welding.
+        let mesh = MeshBuilder::cuboid(Vector3::new(2.0, 2.0, 2.0), 2).build();
+        let field = mesh.signed_distance_field();
+        let cases = [
+            // Nearest a face.
+            (Point3::new(0.0, 0.0, 0.0), -1.0),
+            (Point3::new(0.3, -0.2, 0.5), -0.5),
+            (Point3::new(0.2, 0.1, 3.0), 2.0),
+            (Point3::new(-1.5, 0.5, 0.5), 0.5),
+            // Nearest an edge.
+            (Point3::new(0.9, 0.9, 0.2), -0.1),
+            (Point3::new(2.0, 2.0, 0.3), 2.0f32.sqrt()),
+            (Point3::new(-1.0, 0.5, -2.0), 1.0),
+            // Nearest a corner.
+            (Point3::new(0.95, 0.95, 0.95), -0.05),
+            (Point3::new(2.0, 2.0, 2.0), 3.0f32.sqrt()),
+            (Point3::new(-2.0, 2.0, -3.0), 6.0f32.sqrt()),
+        ];
+        for &(p, expected) in &cases {
+            assert_close(field.signed_distance(p).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn bvh_closest_point_matches_brute_force() {
+        let mesh = MeshBuilder::sphere(1.0, 24, 12).build();
+        let bvh = mesh.bvh();
+        for i in 0..5 {
+            for j in 0..5 {
+                for k in 0..5 {
+                    let p = Point3::new(i as f32 - 2.0, j as f32 - 2.0, k as f32 - 2.0) * 0.6;
+                    let brute_force = mesh
+                        .triangles_iter()
+                        .map(|t| {
+                            let (a, b, c) = t.positions();
+                            let (barycentric, _) = closest_point_on_triangle(p, a, b, c);
+                            (t.position_at(barycentric) - p).magnitude()
+                        })
+                        .fold(f32::INFINITY, f32::min);
+                    assert_close(bvh.closest_point(p).unwrap().distance, brute_force);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_mesh_has_no_closest_point() {
+        let mesh = Mesh::default();
+        assert_eq!(mesh.closest_point(Point3::new(0.0, 0.0, 0.0)), None);
+        assert_eq!(mesh.signed_distance(Point3::new(0.0, 0.0, 0.0)), None);
+    }
+}
@@ -7,13 +7,54 @@ pub type Result<T> = result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
+    Io(std::io::Error),
     Nom(nom_stl::Error),
+    MissingUvs,
+    TangentGeneration,
+    NotMeshBinary,
+    UnsupportedMeshBinaryVersion(u32),
+    MalformedMeshBinary,
+    MisalignedMeshBinary,
+    ChecksumMismatch,
+    MeshTooLargeForBinary,
+    InconsistentMesh,
+    UnsupportedPlatform,
+    NotPly,
+    MalformedPly,
+    MalformedXyz(usize),
+    HeightmapSizeMismatch,
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Error::Io(e) => write!(f, "IO error: {:?}", e),
             Error::Nom(e) => write!(f, "Nom STL error: {:?}", e),
+            Error::MissingUvs => write!(f, "Mesh has no UV coordinates."),
+            Error::TangentGeneration => write!(f, "Mesh is unsuitable for tangent generation."),
+            Error::NotMeshBinary => write!(f, "Data is not in the native mesh binary format."),
+            Error::UnsupportedMeshBinaryVersion(v) => {
+                write!(f, "Unsupported mesh binary format version: {}", v)
+            }
+            Error::MalformedMeshBinary => write!(f, "Mesh binary data is malformed."),
+            Error::MisalignedMeshBinary => write!(f, "Mesh binary data is not 4-byte aligned."),
+            Error::ChecksumMismatch => write!(f, "Mesh binary data does not match its checksum."),
+            Error::MeshTooLargeForBinary => {
+                write!(f, "Mesh is too large for the native mesh binary format.")
+            }
+            Error::InconsistentMesh => write!(
+                f,
+                "Mesh arrays do not match its vertex and triangle counts or contain out of range indices."
+            ),
+            Error::UnsupportedPlatform => {
+                write!(f, "Operation is not supported on this platform.")
+            }
+            Error::NotPly => write!(f, "Data is not in the PLY format."),
+            Error::MalformedPly => write!(f, "PLY data is malformed."),
+            Error::MalformedXyz(line) => write!(f, "XYZ data is malformed on line {}.", line),
+            Error::HeightmapSizeMismatch => {
+                write!(f, "Heightmap pixel count does not match its dimensions.")
+            }
         }
     }
 }
@@ -21,11 +62,32 @@ impl fmt::Display for Error {
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
+            Error::Io(e) => Some(e),
             Error::Nom(e) => Some(e),
+            Error::MissingUvs => None,
+            Error::TangentGeneration => None,
+            Error::NotMeshBinary => None,
+            Error::UnsupportedMeshBinaryVersion(_) => None,
+            Error::MalformedMeshBinary => None,
+            Error::MisalignedMeshBinary => None,
+            Error::ChecksumMismatch => None,
+            Error::MeshTooLargeForBinary => None,
+            Error::InconsistentMesh => None,
+            Error::UnsupportedPlatform => None,
+            Error::NotPly => None,
+            Error::MalformedPly => None,
+            Error::MalformedXyz(_) => None,
+            Error::HeightmapSizeMismatch => None,
         }
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
 impl From<nom_stl::Error> for Error {
     fn from(error: nom_stl::Error) -> Self {
         Error::Nom(error)
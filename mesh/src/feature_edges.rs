@@ -0,0 +1,174 @@
+use super::Mesh;
+use cgmath::{InnerSpace, Rad, Vector3};
+use std::collections::HashMap;
+
+/// The reason that an edge is a feature edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureEdgeKind {
+    /// The edge is shared by two triangles that meet at an angle of at least
+    /// the minimum dihedral angle.
+    Sharp,
+
+    /// The edge belongs to only one triangle.
+    Boundary,
+
+    /// The edge is shared by more than two triangles.
+    NonManifold,
+}
+
+/// An edge along which a surface visibly bends or ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FeatureEdge {
+    /// The indices of the edge's two vertices. The smaller index is first.
+    pub vertices: (usize, usize),
+
+    pub kind: FeatureEdgeKind,
+}
+
+impl Mesh {
+    /// Returns the vertex indices of each feature edge in the mesh. See
+    /// [`Mesh::classify_feature_edges`].
+    pub fn feature_edges<A: Into<Rad<f32>>>(&self, min_dihedral_angle: A) -> Vec<(usize, usize)> {
+        self.classify_feature_edges(min_dihedral_angle)
+            .into_iter()
+            .map(|edge| edge.vertices)
+            .collect()
+    }
+
+    /// Finds the edges where the surface bends by at least
+    /// `min_dihedral_angle`, along with every boundary and non-manifold edge.
+    /// The dihedral angle of an edge is the angle between the normals of its
+    /// two triangles, so it is zero where the surface is flat.
+    ///
+    /// Vertices with identical positions are treated as one vertex, so edges
+    /// between triangles loaded from STL files are found. Each edge refers to
+    /// the first vertex at each of its positions and is returned once, sorted
+    /// by vertex indices. Degenerate triangles are ignored.
+    pub fn classify_feature_edges<A: Into<Rad<f32>>>(
+        &self,
+        min_dihedral_angle: A,
+    ) -> Vec<FeatureEdge> {
+        let min_dihedral_angle = min_dihedral_angle.into();
+        let roots = self.welded_vertex_roots();
+
+        let mut edge_normals: HashMap<(usize, usize), Vec<Vector3<f32>>> = HashMap::new();
+        for &(i1, i2, i3) in &self.triangle_vertex_indices {
+            let nodes = [roots[i1], roots[i2], roots[i3]];
+            let normal = (self.positions[nodes[1]] - self.positions[nodes[0]])
+                .cross(self.positions[nodes[2]] - self.positions[nodes[0]]);
+            if normal.magnitude2() == 0.0 {
+                continue;
+            }
+            for k in 0..3 {
+                let (a, b) = (nodes[k], nodes[(k + 1) % 3]);
+                edge_normals
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push(normal.normalize());
+            }
+        }
+
+        let mut edges: Vec<FeatureEdge> = edge_normals
+            .into_iter()
+            .filter_map(|(vertices, normals)| {
+                let kind = match normals.as_slice() {
+                    [_] => FeatureEdgeKind::Boundary,
+                    [n1, n2] if n1.angle(*n2) >= min_dihedral_angle => FeatureEdgeKind::Sharp,
+                    [_, _] => return None,
+                    _ => FeatureEdgeKind::NonManifold,
+                };
+                Some(FeatureEdge { vertices, kind })
+            })
+            .collect();
+        edges.sort_by_key(|edge| edge.vertices);
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+    use cgmath::{Deg, Point3};
+
+    fn kinds(edges: &[FeatureEdge]) -> Vec<FeatureEdgeKind> {
+        edges.iter().map(|edge| edge.kind).collect()
+    }
+
+    #[test]
+    fn cuboid_has_twelve_sharp_edges() {
+        let cuboid = MeshBuilder::cuboid(Vector3::new(1.0, 2.0, 3.0), 1).build();
+        let edges = cuboid.classify_feature_edges(Deg(30.0));
+        assert_eq!(kinds(&edges), vec![FeatureEdgeKind::Sharp; 12]);
+        for edge in &edges {
+            let (a, b) = edge.vertices;
+            assert!(a < b);
+            // Each edge lies along an edge of the box.
+            let (pa, pb) = (cuboid.positions[a], cuboid.positions[b]);
+            let on_faces = |p: Point3<f32>| {
+                (p.x.abs() == 0.5) as u8 + (p.y.abs() == 1.0) as u8 + (p.z.abs() == 1.5) as u8
+            };
+            assert!(on_faces(pa) >= 2 && on_faces(pb) >= 2);
+        }
+        assert_eq!(cuboid.classify_feature_edges(Deg(100.0)), vec![]);
+    }
+
+    #[test]
+    fn plane_has_four_boundary_edges() {
+        let plane = MeshBuilder::plane(1.0, 1.0, 1, 1).build();
+        let edges = plane.classify_feature_edges(Deg(1.0));
+        assert_eq!(kinds(&edges), vec![FeatureEdgeKind::Boundary; 4]);
+        assert_eq!(
+            plane.feature_edges(Deg(1.0)),
+            edges.iter().map(|edge| edge.vertices).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn three_triangles_on_an_edge_are_non_manifold() {
+        let positions = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.5, 1.0, 0.0),
+            Point3::new(0.5, -1.0, 0.0),
+            Point3::new(0.5, 0.0, 1.0),
+        ];
+        let normals = vec![Vector3::new(0.0, 0.0, 1.0); positions.len()];
+        let mesh =
+            MeshBuilder::new(positions, normals, vec![(0, 1, 2), (1, 0, 3), (0, 1, 4)]).build();
+        let edges = mesh.classify_feature_edges(Deg(30.0));
+        let non_manifold: Vec<_> = edges
+            .iter()
+            .filter(|edge| edge.kind == FeatureEdgeKind::NonManifold)
+            .map(|edge| edge.vertices)
+            .collect();
+        assert_eq!(non_manifold, vec![(0, 1)]);
+        assert_eq!(edges.len(), 7);
+    }
+
+    #[test]
+    fn unwelded_vertices_are_matched() {
+        let cuboid = MeshBuilder::cuboid(Vector3::new(1.0, 1.0, 1.0), 1).build();
+        let corners: Vec<usize> = cuboid
+            .triangle_vertex_indices
+            .iter()
+            .flat_map(|&(i1, i2, i3)| [i1, i2, i3])
+            .collect();
+        let unwelded = MeshBuilder::new(
+            corners.iter().map(|&i| cuboid.positions[i]).collect(),
+            corners.iter().map(|&i| cuboid.normals[i]).collect(),
+            (0..cuboid.triangle_vertex_indices.len())
+                .map(|t| (3 * t, 3 * t + 1, 3 * t + 2))
+                .collect(),
+        )
+        .build();
+
+        let edges = unwelded.classify_feature_edges(Deg(30.0));
+        assert_eq!(kinds(&edges), vec![FeatureEdgeKind::Sharp; 12]);
+        let roots = unwelded.welded_vertex_roots();
+        for edge in &edges {
+            assert_eq!(roots[edge.vertices.0], edge.vertices.0);
+            assert_eq!(roots[edge.vertices.1], edge.vertices.1);
+        }
+    }
+}
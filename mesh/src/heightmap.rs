@@ -0,0 +1,258 @@
+use super::primitives::PrimitiveData;
+use super::{Error, MeshBuilder, Result};
+use cgmath::{InnerSpace, Point3, Vector3};
+
+/// A grayscale image whose pixel values are heights, such as a terrain
+/// elevation map or a lithophane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightmap {
+    /// The number of pixels along the X and Y axes of the image.
+    pub dimensions: (usize, usize),
+
+    /// The value of each pixel, usually in the range `[0, 1]`. The pixel at
+    /// `(x, y)` is at index `x + y * dimensions.0`, and row `0` is the top of
+    /// the image.
+    pub values: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Creates a heightmap from 8-bit grayscale pixels in row-major order.
+    /// Values are scaled to the range `[0, 1]`.
+    pub fn from_luma8(width: usize, height: usize, pixels: &[u8]) -> Result<Self> {
+        Self::from_pixels(width, height, pixels, |p| p as f32 / u8::MAX as f32)
+    }
+
+    /// Creates a heightmap from 16-bit grayscale pixels in row-major order.
+    /// Values are scaled to the range `[0, 1]`.
+    pub fn from_luma16(width: usize, height: usize, pixels: &[u16]) -> Result<Self> {
+        Self::from_pixels(width, height, pixels, |p| p as f32 / u16::MAX as f32)
+    }
+
+    fn from_pixels<T: Copy>(
+        width: usize,
+        height: usize,
+        pixels: &[T],
+        to_value: impl Fn(T) -> f32,
+    ) -> Result<Self> {
+        if pixels.len() != width * height {
+            return Err(Error::HeightmapSizeMismatch);
+        }
+        Ok(Heightmap {
+            dimensions: (width, height),
+            values: pixels.iter().map(|&p| to_value(p)).collect(),
+        })
+    }
+
+    fn value(&self, x: usize, y: usize) -> f32 {
+        self.values[x + y * self.dimensions.0]
+    }
+}
+
+impl MeshBuilder {
+    /// Creates a grid mesh with a vertex for each pixel of the heightmap.
+    /// Adjacent pixels are `scale_xy` apart, and each pixel's height above the
+    /// XY plane is its value times `scale_z`. The grid is centered on the Z
+    /// axis and faces +Z, with the top of the image toward +Y. Normals follow
+    /// the slope of the surface, and the grid is mapped to the full `[0, 1]`
+    /// UV range with V increasing down the image.
+    ///
+    /// If `closed` is true, then side walls down to the XY plane and a flat
+    /// base on the XY plane are added, so the mesh is watertight and can be
+    /// printed. Pixels with a value of zero touch the base, so add an offset to
+    /// the values to give the part a minimum thickness. Walls are left out
+    /// where the boundary pixels are zero, since the surface already meets the
+    /// base there.
+    ///
+    /// Returns an empty mesh if the heightmap is less than two pixels wide or
+    /// tall.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `closed` is true and `scale_z` is negative, since the surface
+    /// would then be below the base and the walls would face inward.
+    pub fn from_heightmap(
+        heightmap: &Heightmap,
+        scale_xy: f32,
+        scale_z: f32,
+        closed: bool,
+    ) -> MeshBuilder {
+        assert!(
+            !closed || scale_z >= 0.0,
+            "a closed heightmap mesh needs a non-negative scale_z"
+        );
+        let (width, height) = heightmap.dimensions;
+        let mut data = PrimitiveData::default();
+        if width < 2 || height < 2 || heightmap.values.len() != width * height {
+            return data.into_builder();
+        }
+
+        let u_of = |x: usize| x as f32 / (width - 1) as f32;
+        let v_of = |y: usize| y as f32 / (height - 1) as f32;
+        let position = |x: usize, y: usize, z: f32| {
+            Point3::new(
+                (x as f32 - (width - 1) as f32 / 2.0) * scale_xy,
+                ((height - 1) as f32 / 2.0 - y as f32) * scale_xy,
+                z,
+            )
+        };
+        let surface_position = |x: usize, y: usize| position(x, y, heightmap.value(x, y) * scale_z);
+
+        // The slope at each pixel comes from central differences, or from
+        // one-sided differences at the edges of the image.
+        for y in 0..height {
+            for x in 0..width {
+                let (x0, x1) = (x.saturating_sub(1), (x + 1).min(width - 1));
+                let (y0, y1) = (y.saturating_sub(1), (y + 1).min(height - 1));
+                let dz_dx = (heightmap.value(x1, y) - heightmap.value(x0, y)) * scale_z
+                    / ((x1 - x0) as f32 * scale_xy);
+                // Y decreases down the image.
+                let dz_dy = (heightmap.value(x, y0) - heightmap.value(x, y1)) * scale_z
+                    / ((y1 - y0) as f32 * scale_xy);
+                let normal = Vector3::new(-dz_dx, -dz_dy, 1.0).normalize();
+                data.push_vertex(surface_position(x, y), normal, u_of(x), v_of(y));
+            }
+        }
+        for y in 0..height - 1 {
+            for x in 0..width - 1 {
+                let a = x + y * width;
+                // Counterclockwise from +Z, starting at the bottom left corner
+                // of the cell.
+                data.push_quad(a + width, a + width + 1, a + 1, a);
+            }
+        }
+
+        if !closed {
+            return data.into_builder();
+        }
+
+        // The boundary of the image, counterclockwise from +Z, as one list of
+        // pixels for each side. Each side starts at the corner where the
+        // previous side ends.
+        let sides: [Vec<(usize, usize)>; 4] = [
+            (0..width).map(|x| (x, height - 1)).collect(),
+            (0..height).rev().map(|y| (width - 1, y)).collect(),
+            (0..width).rev().map(|x| (x, 0)).collect(),
+            (0..height).map(|y| (0, y)).collect(),
+        ];
+
+        for side in &sides {
+            let (first, last) = (side[0], side[side.len() - 1]);
+            let direction = position(last.0, last.1, 0.0) - position(first.0, first.1, 0.0);
+            let normal = Vector3::new(direction.y, -direction.x, 0.0).normalize();
+
+            let start = data.positions_len();
+            for (k, &(x, y)) in side.iter().enumerate() {
+                let u = k as f32 / (side.len() - 1) as f32;
+                data.push_vertex(surface_position(x, y), normal, u, 0.0);
+                data.push_vertex(position(x, y, 0.0), normal, u, 1.0);
+            }
+            for k in 0..side.len() - 1 {
+                let top = start + 2 * k;
+                let (a, b) = (side[k], side[k + 1]);
+                // Where a boundary pixel is zero, its top and bottom vertices
+                // coincide, so the triangle that spans them would have no
+                // area. Where both are zero, the surface meets the base
+                // directly and no wall is needed.
+                if heightmap.value(b.0, b.1) != 0.0 {
+                    data.push_triangle(top + 1, top + 3, top + 2);
+                }
+                if heightmap.value(a.0, a.1) != 0.0 {
+                    data.push_triangle(top + 1, top + 2, top);
+                }
+            }
+        }
+
+        // The base is a fan around its center with a vertex at every boundary
+        // pixel, so that its edges match the bottoms of the walls exactly.
+        let down = Vector3::new(0.0, 0.0, -1.0);
+        let center = data.push_vertex(Point3::new(0.0, 0.0, 0.0), down, 0.5, 0.5);
+        let start = data.positions_len();
+        let boundary: Vec<(usize, usize)> = sides
+            .iter()
+            .flat_map(|side| side[..side.len() - 1].iter().copied())
+            .collect();
+        for &(x, y) in &boundary {
+            data.push_vertex(position(x, y, 0.0), down, u_of(x), v_of(y));
+        }
+        for k in 0..boundary.len() {
+            let next = (k + 1) % boundary.len();
+            data.push_triangle(center, start + next, start + k);
+        }
+
+        data.into_builder()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::assert_closed;
+    use crate::DefectSettings;
+
+    /// A 4×3 heightmap with a black border around two raised pixels, and one
+    /// raised pixel on the border.
+    fn bordered_heightmap() -> Heightmap {
+        #[rustfmt::skip]
+        let pixels = [
+            0, 0, 0, 0,
+            0, 255, 128, 0,
+            0, 0, 64, 0,
+        ];
+        Heightmap::from_luma8(4, 3, &pixels).unwrap()
+    }
+
+    #[test]
+    fn closed_mesh_is_watertight_without_needles() {
+        let heightmap = bordered_heightmap();
+        let mesh = MeshBuilder::from_heightmap(&heightmap, 1.0, 2.0, true).build();
+        assert_closed(&mesh);
+        // Zero pixels touch the base, so only check for degenerate triangles.
+        let settings = DefectSettings::default();
+        assert_eq!(mesh.needle_triangles(settings.min_edge_ratio), vec![]);
+        assert_eq!(mesh.sliver_triangles(settings.max_angle), vec![]);
+    }
+
+    #[test]
+    fn closed_mesh_with_black_border_has_no_walls() {
+        #[rustfmt::skip]
+        let pixels = [
+            0, 0, 0,
+            0, 255, 0,
+            0, 0, 0,
+        ];
+        let heightmap = Heightmap::from_luma8(3, 3, &pixels).unwrap();
+        let mesh = MeshBuilder::from_heightmap(&heightmap, 1.0, 1.0, true).build();
+        assert_closed(&mesh);
+        // The surface grid and the base fan, but no walls.
+        assert_eq!(mesh.triangle_vertex_indices.len(), 8 + 8);
+    }
+
+    #[test]
+    fn closed_mesh_with_raised_border_has_walls() {
+        let heightmap = Heightmap::from_luma8(2, 2, &[255; 4]).unwrap();
+        let mesh = MeshBuilder::from_heightmap(&heightmap, 1.0, 1.0, true).build();
+        assert_closed(&mesh);
+        assert_eq!(mesh.triangle_vertex_indices.len(), 2 + 4 * 2 + 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "a closed heightmap mesh needs a non-negative scale_z")]
+    fn closed_mesh_rejects_negative_scale_z() {
+        MeshBuilder::from_heightmap(&bordered_heightmap(), 1.0, -1.0, true);
+    }
+
+    #[test]
+    fn open_mesh_allows_negative_scale_z() {
+        let mesh = MeshBuilder::from_heightmap(&bordered_heightmap(), 1.0, -1.0, false).build();
+        assert_eq!(mesh.positions.len(), 12);
+        assert!(mesh.positions.iter().all(|p| p.z <= 0.0));
+    }
+
+    #[test]
+    fn rejects_wrong_pixel_count() {
+        assert!(matches!(
+            Heightmap::from_luma16(2, 2, &[0; 3]),
+            Err(Error::HeightmapSizeMismatch)
+        ));
+    }
+}
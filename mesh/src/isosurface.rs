@@ -0,0 +1,349 @@
+use super::MeshBuilder;
+use cgmath::{ElementWise, EuclideanSpace, InnerSpace, Point3, Vector3};
+
+/// Scalar values sampled at the points of a regular 3D grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarGrid {
+    /// The number of grid points along the X, Y, and Z axes.
+    pub dimensions: (usize, usize, usize),
+
+    /// The position of the grid point at `(0, 0, 0)`.
+    pub origin: Point3<f32>,
+
+    /// The distance between adjacent grid points along the X, Y, and Z axes.
+    pub spacing: Vector3<f32>,
+
+    /// The value at each grid point. The value at `(x, y, z)` is at index
+    /// `x + y * dimensions.0 + z * dimensions.0 * dimensions.1`.
+    pub values: Vec<f32>,
+}
+
+impl ScalarGrid {
+    /// Samples `field` on a grid of cubic cells that spans from `min` to `max`.
+    /// `resolution` is the number of cells along the longest side of the box,
+    /// and the other sides get as many cells as are needed to cover it.
+    pub fn sample<F>(field: F, min: Point3<f32>, max: Point3<f32>, resolution: usize) -> Self
+    where
+        F: Fn(Point3<f32>) -> f32,
+    {
+        let extent = max - min;
+        let longest = extent.x.max(extent.y).max(extent.z);
+        let resolution = resolution.max(1);
+        let cell_size = if longest > 0.0 {
+            longest / resolution as f32
+        } else {
+            1.0
+        };
+
+        let dimension = |length: f32| ((length / cell_size).ceil() as usize).max(1) + 1;
+        let dimensions = (
+            dimension(extent.x),
+            dimension(extent.y),
+            dimension(extent.z),
+        );
+        let spacing = Vector3::new(cell_size, cell_size, cell_size);
+
+        let mut values = Vec::with_capacity(dimensions.0 * dimensions.1 * dimensions.2);
+        for z in 0..dimensions.2 {
+            for y in 0..dimensions.1 {
+                for x in 0..dimensions.0 {
+                    let offset = Vector3::new(x as f32, y as f32, z as f32);
+                    values.push(field(min + offset.mul_element_wise(spacing)));
+                }
+            }
+        }
+
+        ScalarGrid {
+            dimensions,
+            origin: min,
+            spacing,
+            values,
+        }
+    }
+
+    /// Returns the value at the grid point `(x, y, z)`.
+    pub fn value(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.values[x + y * self.dimensions.0 + z * self.dimensions.0 * self.dimensions.1]
+    }
+
+    /// Returns the position of the grid point `(x, y, z)`.
+    pub fn position(&self, x: usize, y: usize, z: usize) -> Point3<f32> {
+        let offset = Vector3::new(x as f32, y as f32, z as f32);
+        self.origin + offset.mul_element_wise(self.spacing)
+    }
+
+    /// Returns the gradient at the grid point `(x, y, z)`, estimated with
+    /// central differences in the interior and one-sided differences on the
+    /// boundary.
+    fn gradient(&self, x: usize, y: usize, z: usize) -> Vector3<f32> {
+        let (nx, ny, nz) = self.dimensions;
+        let difference = |i: usize, n: usize, step: f32, at: &dyn Fn(usize) -> f32| {
+            let lo = i.saturating_sub(1);
+            let hi = (i + 1).min(n - 1);
+            if hi == lo {
+                0.0
+            } else {
+                (at(hi) - at(lo)) / ((hi - lo) as f32 * step)
+            }
+        };
+        Vector3::new(
+            difference(x, nx, self.spacing.x, &|i| self.value(i, y, z)),
+            difference(y, ny, self.spacing.y, &|i| self.value(x, i, z)),
+            difference(z, nz, self.spacing.z, &|i| self.value(x, y, i)),
+        )
+    }
+}
+
+/// The offsets of a cell's eight corners from its minimum corner.
+const CORNERS: [(usize, usize, usize); 8] = [
+    (0, 0, 0),
+    (1, 0, 0),
+    (0, 1, 0),
+    (1, 1, 0),
+    (0, 0, 1),
+    (1, 0, 1),
+    (0, 1, 1),
+    (1, 1, 1),
+];
+
+/// The pairs of corners that form a cell's twelve edges.
+const EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+impl MeshBuilder {
+    /// Extracts the surface where `field` equals `iso_value` within the box
+    /// from `min` to `max`. See [`ScalarGrid::sample`] for how `resolution` is
+    /// used and [`MeshBuilder::from_scalar_grid`] for how the surface is
+    /// built.
+    pub fn from_scalar_field<F>(
+        field: F,
+        min: Point3<f32>,
+        max: Point3<f32>,
+        resolution: usize,
+        iso_value: f32,
+    ) -> MeshBuilder
+    where
+        F: Fn(Point3<f32>) -> f32,
+    {
+        MeshBuilder::from_scalar_grid(&ScalarGrid::sample(field, min, max, resolution), iso_value)
+    }
+
+    /// Extracts the surface where the grid's values equal `iso_value`.
+    ///
+    /// Values below `iso_value` are inside the surface, as with a signed
+    /// distance field. Triangles face outward and normals point in the
+    /// direction of the field's gradient.
+    ///
+    /// This uses surface nets, a simple form of dual contouring. Each grid cell
+    /// that the surface passes through gets one vertex at the average of the
+    /// points where the surface crosses the cell's edges, and each grid edge
+    /// that the surface crosses produces a quad between the four cells around
+    /// it. Vertices are shared between quads, so the result is an indexed mesh
+    /// without cracks.
+    pub fn from_scalar_grid(grid: &ScalarGrid, iso_value: f32) -> MeshBuilder {
+        let (nx, ny, nz) = grid.dimensions;
+        let (cx, cy, cz) = (
+            nx.saturating_sub(1),
+            ny.saturating_sub(1),
+            nz.saturating_sub(1),
+        );
+        let cell_index = |x: usize, y: usize, z: usize| x + y * cx + z * cx * cy;
+
+        let mut positions = vec![];
+        let mut normals = vec![];
+        let mut vertex_at_cell = vec![None; cx * cy * cz];
+
+        for z in 0..cz {
+            for y in 0..cy {
+                for x in 0..cx {
+                    let corner = |k: usize| {
+                        let (dx, dy, dz) = CORNERS[k];
+                        (x + dx, y + dy, z + dz)
+                    };
+                    let values: Vec<f32> = (0..8)
+                        .map(|k| {
+                            let (px, py, pz) = corner(k);
+                            grid.value(px, py, pz)
+                        })
+                        .collect();
+
+                    let inside_count = values.iter().filter(|&&v| v < iso_value).count();
+                    if inside_count == 0 || inside_count == 8 {
+                        continue;
+                    }
+
+                    let mut sum = Vector3::new(0.0, 0.0, 0.0);
+                    let mut gradient = Vector3::new(0.0, 0.0, 0.0);
+                    let mut crossings = 0;
+                    for &(a, b) in &EDGES {
+                        let (va, vb) = (values[a], values[b]);
+                        if (va < iso_value) == (vb < iso_value) {
+                            continue;
+                        }
+
+                        let t = (iso_value - va) / (vb - va);
+                        let (ax, ay, az) = corner(a);
+                        let (bx, by, bz) = corner(b);
+                        let pa = grid.position(ax, ay, az);
+                        let pb = grid.position(bx, by, bz);
+                        sum += (pa + (pb - pa) * t).to_vec();
+
+                        let ga = grid.gradient(ax, ay, az);
+                        let gb = grid.gradient(bx, by, bz);
+                        gradient += ga + (gb - ga) * t;
+                        crossings += 1;
+                    }
+
+                    let normal = if gradient.magnitude2() > 0.0 {
+                        gradient.normalize()
+                    } else {
+                        gradient
+                    };
+                    vertex_at_cell[cell_index(x, y, z)] = Some(positions.len());
+                    positions.push(Point3::from_vec(sum / crossings as f32));
+                    normals.push(normal);
+                }
+            }
+        }
+
+        // Every grid edge that the surface crosses, and that is surrounded by
+        // four cells, produces a quad. An edge along axis `a` is surrounded by
+        // the cells that are offset from it by zero or minus one along the
+        // other two axes, `b` and `c`, which follow `a` in cyclic order so that
+        // the quad's corners wind counterclockwise around `a`.
+        let mut triangle_vertex_indices = vec![];
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let start_inside = grid.value(x, y, z) < iso_value;
+                    for axis in 0..3 {
+                        let p = [x, y, z];
+                        let n = [nx, ny, nz];
+                        let b = (axis + 1) % 3;
+                        let c = (axis + 2) % 3;
+                        let is_surrounded =
+                            p[b] > 0 && p[c] > 0 && p[b] + 1 < n[b] && p[c] + 1 < n[c];
+                        if p[axis] + 1 >= n[axis] || !is_surrounded {
+                            continue;
+                        }
+
+                        let mut end = p;
+                        end[axis] += 1;
+                        let end_inside = grid.value(end[0], end[1], end[2]) < iso_value;
+                        if start_inside == end_inside {
+                            continue;
+                        }
+
+                        let cell = |db: usize, dc: usize| {
+                            let mut q = p;
+                            q[b] = q[b] + db - 1;
+                            q[c] = q[c] + dc - 1;
+                            vertex_at_cell[cell_index(q[0], q[1], q[2])]
+                        };
+                        let corners = [cell(0, 0), cell(1, 0), cell(1, 1), cell(0, 1)];
+                        let (v0, v1, v2, v3) = match corners {
+                            [Some(v0), Some(v1), Some(v2), Some(v3)] => (v0, v1, v2, v3),
+                            _ => continue,
+                        };
+
+                        // The surface faces outward, toward increasing values.
+                        if start_inside {
+                            triangle_vertex_indices.push((v0, v1, v2));
+                            triangle_vertex_indices.push((v0, v2, v3));
+                        } else {
+                            triangle_vertex_indices.push((v0, v2, v1));
+                            triangle_vertex_indices.push((v0, v3, v2));
+                        }
+                    }
+                }
+            }
+        }
+
+        MeshBuilder::new(positions, normals, triangle_vertex_indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::assert_closed;
+    use cgmath::{EuclideanSpace, InnerSpace};
+
+    #[test]
+    fn sphere_field_gives_closed_sphere() {
+        let radius = 0.8;
+        let min = Point3::new(-1.0, -1.0, -1.0);
+        let max = Point3::new(1.0, 1.0, 1.0);
+        let resolution = 16;
+        let cell_size = 2.0 / resolution as f32;
+        let mesh = MeshBuilder::from_scalar_field(
+            |p| p.to_vec().magnitude() - radius,
+            min,
+            max,
+            resolution,
+            0.0,
+        )
+        .build();
+
+        assert!(!mesh.triangle_vertex_indices.is_empty());
+        assert_closed(&mesh);
+        assert_eq!(mesh.connected_components().len(), 1);
+        for p in &mesh.positions {
+            let distance = p.to_vec().magnitude();
+            assert!(
+                (distance - radius).abs() < cell_size,
+                "vertex {:?} is {} from the center",
+                p,
+                distance
+            );
+        }
+
+        // Triangles face outward and normals follow the gradient.
+        for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+            assert!(n.dot(p.to_vec()) > 0.0);
+        }
+        for triangle in mesh.triangles_iter() {
+            let (p1, p2, p3) = triangle.positions();
+            let normal = (p2 - p1).cross(p3 - p1);
+            assert!(normal.dot(p1.to_vec()) > 0.0);
+        }
+    }
+
+    #[test]
+    fn sample_covers_the_box() {
+        let grid = ScalarGrid::sample(
+            |p| p.x + 10.0 * p.y + 100.0 * p.z,
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(2.0, 1.0, 0.5),
+            4,
+        );
+        assert_eq!(grid.dimensions, (5, 3, 2));
+        assert_eq!(grid.position(4, 2, 1), Point3::new(2.0, 1.0, 0.5));
+        assert_eq!(grid.value(1, 2, 1), 0.5 + 10.0 + 50.0);
+    }
+
+    #[test]
+    fn field_without_crossings_is_empty() {
+        let mesh = MeshBuilder::from_scalar_field(
+            |_| 1.0,
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 1.0),
+            4,
+            0.0,
+        )
+        .build();
+        assert!(mesh.triangle_vertex_indices.is_empty());
+    }
+}
@@ -1,13 +1,59 @@
+mod ambient_occlusion;
+mod attribute;
+mod binary;
+mod bvh;
+mod color_map;
+mod comparison;
+mod components;
+mod curvature;
+mod defects;
+mod distance;
 mod error;
+mod feature_edges;
+mod heightmap;
+mod isosurface;
+mod lod;
+mod material;
+mod merge;
+mod ply;
+mod point_cloud;
+mod primitives;
+mod printability;
+mod ray;
+mod tangents;
 mod triangle;
+mod uv;
+mod vertex_cache;
+mod voxel;
 
+pub use ambient_occlusion::{AmbientOcclusionSettings, AMBIENT_OCCLUSION_ATTRIBUTE};
+pub use attribute::AttributeData;
+pub use binary::{AttributeView, MeshView};
+pub use bvh::Bvh;
+pub use color_map::color_map;
+pub use comparison::{ComparisonSettings, MeshComparison, DEVIATION_ATTRIBUTE};
+pub use curvature::Curvature;
+pub use defects::{DefectReport, DefectSettings};
+pub use distance::{ClosestPoint, SignedDistanceField};
 pub use error::{Error, Result};
+pub use feature_edges::{FeatureEdge, FeatureEdgeKind};
+pub use heightmap::Heightmap;
+pub use isosurface::ScalarGrid;
+pub use lod::{choose_level_of_detail, LevelOfDetail};
+pub use material::Material;
+pub use point_cloud::PointCloud;
+pub use printability::{PrintabilityReport, PrintabilitySettings};
+pub use ray::RayHit;
 pub use triangle::Triangle;
+pub use uv::Axis;
+pub use voxel::{VoxelGrid, VoxelizeMode};
 
-use cgmath::{InnerSpace, Matrix4, Point2, Point3, Transform, Vector3};
+use cgmath::{InnerSpace, Matrix3, Matrix4, Point2, Point3, Rad, Transform, Vector3, Vector4};
+use std::collections::HashMap;
 
 /// A mesh of triangles.
-#[derive(Debug)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mesh {
     /// Contains a position for each vertex in the mesh.
     pub positions: Vec<Point3<f32>>,
@@ -18,21 +64,90 @@ pub struct Mesh {
     /// Contains a UV coordinate for each vertex in the mesh.
     pub uvs: Option<Vec<Point2<f32>>>,
 
+    /// Contains a tangent vector for each vertex in the mesh. The W component
+    /// is the sign of the bitangent, which is computed as
+    /// `cross(normal, tangent.xyz) * tangent.w`.
+    pub tangents: Option<Vec<Vector4<f32>>>,
+
+    /// Contains a linear RGBA color for each vertex in the mesh. Each component
+    /// is in the range `[0, 1]`.
+    pub colors: Option<Vec<Vector4<f32>>>,
+
+    /// Contains named custom attributes. Each attribute has a value for each
+    /// vertex in the mesh.
+    pub attributes: HashMap<String, AttributeData>,
+
     /// An array that describes each triangle in the mesh. Each element of the
     /// array is a tuple that contains three indices into the `vertices` array.
     pub triangle_vertex_indices: Vec<(usize, usize, usize)>,
+
+    /// Contains the materials that are used by the mesh's triangles.
+    pub materials: Vec<Material>,
+
+    /// Contains an index into the `materials` array for each triangle in the
+    /// mesh.
+    pub triangle_materials: Option<Vec<usize>>,
 }
 
 impl Mesh {
-    /// Apply the transformation matrix to the position and normal of each
-    /// vertex in the mesh.
+    /// Apply the transformation matrix to the position, normal, and tangent of
+    /// each vertex in the mesh.
+    ///
+    /// Normals are transformed by the inverse transpose of the matrix so that
+    /// they stay perpendicular to the surface under non-uniform scales and
+    /// shears. If the transformation mirrors the mesh, then the winding of each
+    /// triangle is reversed so that front faces stay front faces.
     pub fn transform(&mut self, transformation: Matrix4<f32>) {
         for p in &mut self.positions {
             *p = transformation.transform_point(*p);
         }
 
+        let is_mirroring = is_mirroring(transformation);
+        let normal_matrix = normal_matrix(transformation);
         for n in &mut self.normals {
-            *n = transformation.transform_vector(*n).normalize();
+            *n = (normal_matrix * *n).normalize();
+        }
+
+        if let Some(tangents) = &mut self.tangents {
+            for t in tangents {
+                let xyz = transformation.transform_vector(t.truncate()).normalize();
+                let w = if is_mirroring { -t.w } else { t.w };
+                *t = xyz.extend(w);
+            }
+        }
+
+        if is_mirroring {
+            for (_, i2, i3) in &mut self.triangle_vertex_indices {
+                std::mem::swap(i2, i3);
+            }
+        }
+    }
+
+    /// Moves the mesh by the given offset.
+    pub fn translate(&mut self, offset: Vector3<f32>) {
+        self.transform(Matrix4::from_translation(offset));
+    }
+
+    /// Scales the mesh about the origin by the given factor along each axis.
+    /// Negative factors mirror the mesh.
+    pub fn scale(&mut self, factors: Vector3<f32>) {
+        self.transform(Matrix4::from_nonuniform_scale(
+            factors.x, factors.y, factors.z,
+        ));
+    }
+
+    /// Rotates the mesh about an axis through the origin. The axis does not
+    /// need to be normalized.
+    pub fn rotate<A: Into<Rad<f32>>>(&mut self, axis: Vector3<f32>, angle: A) {
+        self.transform(Matrix4::from_axis_angle(axis.normalize(), angle));
+    }
+
+    /// Moves the mesh so that the center of its bounding box is at the origin.
+    /// Does nothing if the mesh is empty.
+    pub fn center_at_origin(&mut self) {
+        if let Some((min, max)) = self.bounding_box() {
+            let center = min + (max - min) / 2.0;
+            self.translate(Point3::new(0.0, 0.0, 0.0) - center);
         }
     }
 
@@ -71,11 +186,47 @@ impl Mesh {
     }
 }
 
+/// Returns true if the transformation mirrors whatever it is applied to, which
+/// reverses the winding of triangles.
+pub fn is_mirroring(transformation: Matrix4<f32>) -> bool {
+    let (a, b, c) = linear_columns(transformation);
+    a.dot(b.cross(c)) < 0.0
+}
+
+/// Returns the matrix that transforms normals under the transformation, which
+/// is the inverse transpose of its linear part, except that it is not divided
+/// by the magnitude of the determinant. That only scales the normals, which
+/// must be normalized afterwards anyway, and it keeps this well defined for
+/// singular transformations.
+pub(crate) fn normal_matrix(transformation: Matrix4<f32>) -> Matrix3<f32> {
+    let (a, b, c) = linear_columns(transformation);
+    let sign = if is_mirroring(transformation) {
+        -1.0
+    } else {
+        1.0
+    };
+    Matrix3::from_cols(b.cross(c), c.cross(a), a.cross(b)) * sign
+}
+
+/// Returns the columns of the linear part of the transformation.
+fn linear_columns(transformation: Matrix4<f32>) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>) {
+    (
+        transformation.x.truncate(),
+        transformation.y.truncate(),
+        transformation.z.truncate(),
+    )
+}
+
 pub struct MeshBuilder {
     positions: Vec<Point3<f32>>,
     normals: Vec<Vector3<f32>>,
     uvs: Option<Vec<Point2<f32>>>,
+    tangents: Option<Vec<Vector4<f32>>>,
+    colors: Option<Vec<Vector4<f32>>>,
+    attributes: HashMap<String, AttributeData>,
     triangle_vertex_indices: Vec<(usize, usize, usize)>,
+    materials: Vec<Material>,
+    triangle_materials: Option<Vec<usize>>,
 }
 
 impl MeshBuilder {
@@ -88,7 +239,12 @@ impl MeshBuilder {
             positions,
             normals,
             uvs: None,
+            tangents: None,
+            colors: None,
+            attributes: HashMap::new(),
             triangle_vertex_indices,
+            materials: vec![],
+            triangle_materials: None,
         }
     }
 
@@ -97,12 +253,57 @@ impl MeshBuilder {
         self
     }
 
+    pub fn tangents(mut self, tangents: Vec<Vector4<f32>>) -> Self {
+        self.tangents = Some(tangents);
+        self
+    }
+
+    pub fn colors(mut self, colors: Vec<Vector4<f32>>) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn attribute(mut self, name: &str, data: AttributeData) -> Self {
+        self.attributes.insert(name.to_string(), data);
+        self
+    }
+
+    /// Sets the mesh's materials and the index into `materials` of each
+    /// triangle's material.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `triangle_materials` does not have an index for each
+    /// triangle or if an index is out of range.
+    pub fn materials(mut self, materials: Vec<Material>, triangle_materials: Vec<usize>) -> Self {
+        assert_eq!(
+            triangle_materials.len(),
+            self.triangle_vertex_indices.len(),
+            "expected a material index for each triangle"
+        );
+        if let Some(&m) = triangle_materials.iter().find(|&&m| m >= materials.len()) {
+            panic!(
+                "material index {} is out of range for {} materials",
+                m,
+                materials.len()
+            );
+        }
+        self.materials = materials;
+        self.triangle_materials = Some(triangle_materials);
+        self
+    }
+
     pub fn build(self) -> Mesh {
         Mesh {
             positions: self.positions,
             normals: self.normals,
             uvs: self.uvs,
+            tangents: self.tangents,
+            colors: self.colors,
+            attributes: self.attributes,
             triangle_vertex_indices: self.triangle_vertex_indices,
+            materials: self.materials,
+            triangle_materials: self.triangle_materials,
         }
     }
 
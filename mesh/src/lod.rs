@@ -0,0 +1,620 @@
+use super::Mesh;
+use cgmath::{InnerSpace, Point3, Rad, Vector3};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+
+/// A simplified version of a mesh.
+#[derive(Debug)]
+pub struct LevelOfDetail {
+    /// The simplified mesh.
+    pub mesh: Mesh,
+
+    /// An estimate of how far the simplified surface deviates from the
+    /// original surface, in the mesh's units. This is zero for a level that was
+    /// not simplified.
+    pub geometric_error: f32,
+}
+
+impl LevelOfDetail {
+    /// Returns the approximate number of pixels by which the simplified
+    /// surface deviates from the original surface when it is rendered at
+    /// `distance` from a perspective camera with the vertical field of view
+    /// `fovy` into a viewport that is `viewport_height` pixels tall.
+    pub fn screen_space_error<A: Into<Rad<f32>>>(
+        &self,
+        distance: f32,
+        fovy: A,
+        viewport_height: u32,
+    ) -> f32 {
+        let Rad(fovy) = fovy.into();
+        let visible_height = 2.0 * distance.max(f32::EPSILON) * (fovy / 2.0).tan();
+        self.geometric_error * viewport_height as f32 / visible_height
+    }
+}
+
+/// Returns the index of the coarsest level whose screen-space error is at most
+/// `max_pixel_error`, or of the finest level if none of them are accurate
+/// enough. Levels are expected to be ordered from finest to coarsest, as
+/// returned by [`Mesh::lod_chain`]. Returns `None` if there are no levels.
+pub fn choose_level_of_detail<A: Into<Rad<f32>>>(
+    levels: &[LevelOfDetail],
+    distance: f32,
+    fovy: A,
+    viewport_height: u32,
+    max_pixel_error: f32,
+) -> Option<usize> {
+    let fovy = fovy.into();
+    if levels.is_empty() {
+        return None;
+    }
+    let index = levels
+        .iter()
+        .rposition(|level| {
+            level.screen_space_error(distance, fovy, viewport_height) <= max_pixel_error
+        })
+        .unwrap_or(0);
+    Some(index)
+}
+
+impl Mesh {
+    /// Builds a chain of progressively simplified meshes. Each ratio is the
+    /// fraction of the mesh's triangles that its level should keep, so a ratio
+    /// of `1.0` keeps the full mesh and `0.25` keeps about a quarter of it.
+    /// Levels are returned from finest to coarsest, regardless of the order of
+    /// `ratios`.
+    ///
+    /// See [`Mesh::simplify`] for how the meshes are simplified. All levels are
+    /// produced by a single simplification pass, so each level is a
+    /// simplification of the levels before it.
+    pub fn lod_chain(&self, ratios: &[f32]) -> Vec<LevelOfDetail> {
+        let mut ratios = ratios.to_vec();
+        ratios.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+        let triangle_count = self.triangle_vertex_indices.len();
+        let mut simplifier = Simplifier::new(self);
+        ratios
+            .into_iter()
+            .map(|ratio| {
+                let target = (triangle_count as f32 * ratio.clamp(0.0, 1.0)).round();
+                simplifier.simplify_to(target as usize);
+                simplifier.level_of_detail()
+            })
+            .collect()
+    }
+
+    /// Simplifies the mesh until it has at most `target_triangle_count`
+    /// triangles, or until it cannot be simplified any further without folding
+    /// triangles over.
+    ///
+    /// This collapses edges in order of their quadric error, as described in
+    /// Garland and Heckbert's "Surface Simplification Using Quadric Error
+    /// Metrics". Vertices with identical positions are treated as one vertex,
+    /// so meshes loaded from STL files are simplified correctly, and the
+    /// vertex attributes on either side of a UV or normal seam are kept apart.
+    /// Moved vertices keep their original normals and other attributes.
+    pub fn simplify(&self, target_triangle_count: usize) -> LevelOfDetail {
+        let mut simplifier = Simplifier::new(self);
+        simplifier.simplify_to(target_triangle_count);
+        simplifier.level_of_detail()
+    }
+}
+
+/// A symmetric 4x4 matrix that measures the sum of the squared distances from
+/// a point to a set of planes. The ten unique entries are stored row by row.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Returns the quadric of the plane through `p` with the unit normal `n`.
+    fn from_plane(n: Vector3<f64>, p: Point3<f64>) -> Self {
+        let d = -(n.x * p.x + n.y * p.y + n.z * p.z);
+        Quadric([
+            n.x * n.x,
+            n.x * n.y,
+            n.x * n.z,
+            n.x * d,
+            n.y * n.y,
+            n.y * n.z,
+            n.y * d,
+            n.z * n.z,
+            n.z * d,
+            d * d,
+        ])
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a += b;
+        }
+    }
+
+    fn sum(&self, other: &Quadric) -> Quadric {
+        let mut sum = *self;
+        sum.add(other);
+        sum
+    }
+
+    /// Returns the sum of the squared distances from `p` to the planes.
+    fn error(&self, p: Point3<f64>) -> f64 {
+        let q = &self.0;
+        let error = q[0] * p.x * p.x
+            + 2.0 * q[1] * p.x * p.y
+            + 2.0 * q[2] * p.x * p.z
+            + 2.0 * q[3] * p.x
+            + q[4] * p.y * p.y
+            + 2.0 * q[5] * p.y * p.z
+            + 2.0 * q[6] * p.y
+            + q[7] * p.z * p.z
+            + 2.0 * q[8] * p.z
+            + q[9];
+        error.max(0.0)
+    }
+
+    /// Returns the point that minimizes the error, or `None` if there is no
+    /// unique minimum, such as when all of the planes are parallel.
+    fn minimizer(&self) -> Option<Point3<f64>> {
+        let q = &self.0;
+        let (a, b, c, e, f, h) = (q[0], q[1], q[2], q[4], q[5], q[7]);
+        let det = a * (e * h - f * f) - b * (b * h - f * c) + c * (b * f - e * c);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+
+        // Solve the linear system with Cramer's rule.
+        let (r0, r1, r2) = (-q[3], -q[6], -q[8]);
+        let x = (r0 * (e * h - f * f) - b * (r1 * h - f * r2) + c * (r1 * f - e * r2)) / det;
+        let y = (a * (r1 * h - f * r2) - r0 * (b * h - f * c) + c * (b * r2 - r1 * c)) / det;
+        let z = (a * (e * r2 - r1 * f) - b * (b * r2 - r1 * c) + r0 * (b * f - e * c)) / det;
+        Some(Point3::new(x, y, z))
+    }
+}
+
+/// A candidate edge collapse in the priority queue. Candidates are ordered so
+/// that the cheapest one is popped first.
+struct Collapse {
+    cost: f64,
+    from: usize,
+    to: usize,
+    from_version: u32,
+    to_version: u32,
+    position: Point3<f64>,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// The state of an in-progress simplification.
+///
+/// Vertices with identical positions are grouped into nodes, and each node is
+/// identified by the index of its first vertex. Collapsing an edge merges one
+/// node into another. Each vertex of the removed node that shares a triangle
+/// with the edge is replaced by its partner vertex across the edge, and any
+/// other vertices, such as those on the far side of a seam, are kept but moved.
+struct Simplifier<'msh> {
+    mesh: &'msh Mesh,
+
+    /// The node of each vertex, before any collapses.
+    roots: Vec<usize>,
+
+    /// The node that each node has been merged into, or the node itself if it
+    /// has not been merged.
+    merged_into: Vec<usize>,
+
+    /// The vertex that each vertex has been replaced by, or the vertex itself
+    /// if it has not been replaced.
+    replaced_by: Vec<usize>,
+
+    positions: Vec<Point3<f64>>,
+    quadrics: Vec<Quadric>,
+
+    /// Incremented whenever a node changes, so that stale candidates in the
+    /// queue can be recognized.
+    versions: Vec<u32>,
+
+    /// The triangles around each node. This may include triangles that have
+    /// since been removed.
+    node_triangles: Vec<Vec<usize>>,
+
+    is_removed: Vec<bool>,
+    triangle_count: usize,
+    queue: BinaryHeap<Collapse>,
+    max_error: f64,
+}
+
+impl<'msh> Simplifier<'msh> {
+    fn new(mesh: &'msh Mesh) -> Self {
+        let roots = mesh.welded_vertex_roots();
+        let node_count = mesh.positions.len();
+        let positions = mesh
+            .positions
+            .iter()
+            .map(|p| Point3::new(p.x as f64, p.y as f64, p.z as f64))
+            .collect();
+
+        let mut simplifier = Simplifier {
+            mesh,
+            roots,
+            merged_into: (0..node_count).collect(),
+            replaced_by: (0..node_count).collect(),
+            positions,
+            quadrics: vec![Quadric::default(); node_count],
+            versions: vec![0; node_count],
+            node_triangles: vec![vec![]; node_count],
+            is_removed: vec![false; mesh.triangle_vertex_indices.len()],
+            triangle_count: 0,
+            queue: BinaryHeap::new(),
+            max_error: 0.0,
+        };
+
+        // Each node starts with the planes of the triangles around it. Edges
+        // on the boundary of the mesh also get a plane that is perpendicular
+        // to their triangle, which keeps the boundary from shrinking. Edges are
+        // visited in a fixed order so that collapses with equal costs are
+        // always made in the same order.
+        let mut edge_triangles: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+        for t in 0..mesh.triangle_vertex_indices.len() {
+            let nodes = simplifier.triangle_nodes(t);
+            if nodes[0] == nodes[1] || nodes[1] == nodes[2] || nodes[2] == nodes[0] {
+                simplifier.is_removed[t] = true;
+                continue;
+            }
+            simplifier.triangle_count += 1;
+
+            let normal = simplifier.triangle_normal(nodes, None).normalize();
+            if normal.x.is_finite() {
+                let plane = Quadric::from_plane(normal, simplifier.positions[nodes[0]]);
+                for &node in &nodes {
+                    simplifier.quadrics[node].add(&plane);
+                }
+            }
+
+            for k in 0..3 {
+                let (a, b) = (nodes[k], nodes[(k + 1) % 3]);
+                simplifier.node_triangles[a].push(t);
+                edge_triangles
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push(t);
+            }
+        }
+
+        for (&(a, b), triangles) in &edge_triangles {
+            if triangles.len() == 1 {
+                let nodes = simplifier.triangle_nodes(triangles[0]);
+                let normal = simplifier.triangle_normal(nodes, None);
+                let edge = simplifier.positions[b] - simplifier.positions[a];
+                let perpendicular = edge.cross(normal).normalize();
+                if perpendicular.x.is_finite() {
+                    let plane = Quadric::from_plane(perpendicular, simplifier.positions[a]);
+                    simplifier.quadrics[a].add(&plane);
+                    simplifier.quadrics[b].add(&plane);
+                }
+            }
+            simplifier.push_candidate(a, b);
+        }
+
+        simplifier
+    }
+
+    /// Collapses edges until there are at most `target_triangle_count`
+    /// triangles or no valid collapses remain.
+    fn simplify_to(&mut self, target_triangle_count: usize) {
+        while self.triangle_count > target_triangle_count {
+            let collapse = match self.queue.pop() {
+                Some(collapse) => collapse,
+                None => return,
+            };
+            let (from, to) = (collapse.from, collapse.to);
+            let is_stale = self.merged_into[from] != from
+                || self.merged_into[to] != to
+                || self.versions[from] != collapse.from_version
+                || self.versions[to] != collapse.to_version;
+            if is_stale || self.would_fold_over(&collapse) {
+                continue;
+            }
+
+            self.max_error = self.max_error.max(collapse.cost);
+            self.replace_vertices_across(from, to);
+            self.merged_into[from] = to;
+            self.positions[to] = collapse.position;
+            self.quadrics[to] = self.quadrics[to].sum(&self.quadrics[from]);
+            self.versions[to] += 1;
+
+            let mut triangles = std::mem::take(&mut self.node_triangles[from]);
+            triangles.append(&mut self.node_triangles[to]);
+            triangles.sort_unstable();
+            triangles.dedup();
+
+            let mut neighbors = vec![];
+            for &t in &triangles {
+                if self.is_removed[t] {
+                    continue;
+                }
+                let nodes = self.triangle_nodes(t);
+                if nodes[0] == nodes[1] || nodes[1] == nodes[2] || nodes[2] == nodes[0] {
+                    self.is_removed[t] = true;
+                    self.triangle_count -= 1;
+                    continue;
+                }
+                neighbors.extend(nodes.iter().copied().filter(|&node| node != to));
+            }
+            triangles.retain(|&t| !self.is_removed[t]);
+            self.node_triangles[to] = triangles;
+
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            for neighbor in neighbors {
+                self.push_candidate(to, neighbor);
+            }
+        }
+    }
+
+    /// Returns a copy of the mesh in its current state of simplification.
+    fn level_of_detail(&mut self) -> LevelOfDetail {
+        let remaining: Vec<usize> = (0..self.is_removed.len())
+            .filter(|&t| !self.is_removed[t])
+            .collect();
+        let triangles: Vec<_> = remaining
+            .iter()
+            .map(|&t| {
+                let (i1, i2, i3) = self.mesh.triangle_vertex_indices[t];
+                (self.vertex(i1), self.vertex(i2), self.vertex(i3))
+            })
+            .collect();
+        let mut mesh = self.mesh.with_triangles(&remaining, &triangles);
+
+        for (&(n1, n2, n3), &(o1, o2, o3)) in mesh.triangle_vertex_indices.iter().zip(&triangles) {
+            for &(new, old) in &[(n1, o1), (n2, o2), (n3, o3)] {
+                let node = self.node(old);
+                let p = self.positions[node];
+                mesh.positions[new] = Point3::new(p.x as f32, p.y as f32, p.z as f32);
+            }
+        }
+
+        LevelOfDetail {
+            mesh,
+            geometric_error: self.max_error.sqrt() as f32,
+        }
+    }
+
+    /// Before the node `from` is merged into `to`, replaces each vertex of
+    /// `from` in a triangle along the edge with that triangle's vertex of `to`.
+    fn replace_vertices_across(&mut self, from: usize, to: usize) {
+        let triangles = self.node_triangles[from].clone();
+        for t in triangles {
+            if self.is_removed[t] {
+                continue;
+            }
+            let (i1, i2, i3) = self.mesh.triangle_vertex_indices[t];
+            let corners = [i1, i2, i3];
+            let nodes = self.triangle_nodes(t);
+            let from_corner = nodes.iter().position(|&node| node == from);
+            let to_corner = nodes.iter().position(|&node| node == to);
+            if let (Some(a), Some(b)) = (from_corner, to_corner) {
+                let from_vertex = self.vertex(corners[a]);
+                let to_vertex = self.vertex(corners[b]);
+                if from_vertex != to_vertex && self.replaced_by[from_vertex] == from_vertex {
+                    self.replaced_by[from_vertex] = to_vertex;
+                }
+            }
+        }
+    }
+
+    /// Returns the vertex that currently stands in for the given vertex.
+    fn vertex(&mut self, vertex: usize) -> usize {
+        let mut vertex = vertex;
+        while self.replaced_by[vertex] != vertex {
+            let next = self.replaced_by[vertex];
+            self.replaced_by[vertex] = self.replaced_by[next];
+            vertex = next;
+        }
+        vertex
+    }
+
+    /// Returns the node that the vertex currently belongs to.
+    fn node(&mut self, vertex: usize) -> usize {
+        let mut node = self.roots[vertex];
+        while self.merged_into[node] != node {
+            let next = self.merged_into[node];
+            self.merged_into[node] = self.merged_into[next];
+            node = next;
+        }
+        node
+    }
+
+    fn triangle_nodes(&mut self, t: usize) -> [usize; 3] {
+        let (i1, i2, i3) = self.mesh.triangle_vertex_indices[t];
+        [self.node(i1), self.node(i2), self.node(i3)]
+    }
+
+    /// Returns the unnormalized normal of the triangle with the given nodes.
+    /// If `moved` is given, then that node is treated as being at the given
+    /// position instead.
+    fn triangle_normal(
+        &self,
+        nodes: [usize; 3],
+        moved: Option<(usize, Point3<f64>)>,
+    ) -> Vector3<f64> {
+        let position = |node: usize| match moved {
+            Some((moved_node, p)) if moved_node == node => p,
+            _ => self.positions[node],
+        };
+        let (a, b, c) = (position(nodes[0]), position(nodes[1]), position(nodes[2]));
+        (b - a).cross(c - a)
+    }
+
+    /// Adds a candidate for collapsing the edge between the two nodes, in
+    /// whichever direction has the lower error.
+    fn push_candidate(&mut self, a: usize, b: usize) {
+        let quadric = self.quadrics[a].sum(&self.quadrics[b]);
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        let midpoint = pa + (pb - pa) / 2.0;
+
+        let (cost, position) = quadric
+            .minimizer()
+            .into_iter()
+            .chain(vec![pa, pb, midpoint])
+            .map(|p| (quadric.error(p), p))
+            .min_by(|x, y| x.0.total_cmp(&y.0))
+            .unwrap();
+
+        self.queue.push(Collapse {
+            cost,
+            from: a,
+            to: b,
+            from_version: self.versions[a],
+            to_version: self.versions[b],
+            position,
+        });
+    }
+
+    /// Returns true if the collapse would flip any of the triangles that
+    /// remain around the edge.
+    fn would_fold_over(&mut self, collapse: &Collapse) -> bool {
+        let triangles: Vec<usize> = self.node_triangles[collapse.from]
+            .iter()
+            .chain(self.node_triangles[collapse.to].iter())
+            .copied()
+            .filter(|&t| !self.is_removed[t])
+            .collect();
+
+        triangles.into_iter().any(|t| {
+            let nodes = self.triangle_nodes(t);
+            if nodes.contains(&collapse.from) && nodes.contains(&collapse.to) {
+                return false;
+            }
+            let before = self.triangle_normal(nodes, None);
+            let moved = |node: usize| {
+                if node == collapse.from {
+                    collapse.to
+                } else {
+                    node
+                }
+            };
+            let after = self.triangle_normal(
+                [moved(nodes[0]), moved(nodes[1]), moved(nodes[2])],
+                Some((collapse.to, collapse.position)),
+            );
+            before.dot(after) <= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::assert_closed;
+    use crate::MeshBuilder;
+    use cgmath::{Deg, Vector3};
+
+    #[test]
+    fn simplify_reaches_target_triangle_count() {
+        let sphere = MeshBuilder::sphere(1.0, 32, 16).build();
+        let level = sphere.simplify(100);
+        let triangle_count = level.mesh.triangle_vertex_indices.len();
+        assert!(
+            (90..=100).contains(&triangle_count),
+            "simplified to {} triangles",
+            triangle_count
+        );
+        assert!(level.geometric_error > 0.0);
+        assert_closed(&level.mesh);
+    }
+
+    #[test]
+    fn simplified_cuboid_keeps_its_bounds_and_stays_closed() {
+        let cuboid = MeshBuilder::cuboid(Vector3::new(1.0, 2.0, 3.0), 4).build();
+        // Collapsing the vertices in the middle of each face costs nothing.
+        let level = cuboid.simplify(24);
+        assert!(level.mesh.triangle_vertex_indices.len() <= 24);
+        assert_eq!(level.geometric_error, 0.0);
+        assert_eq!(level.mesh.bounding_box(), cuboid.bounding_box());
+        let area: f32 = level.mesh.triangles_iter().map(|t| t.area()).sum();
+        assert!((area - 22.0).abs() < 1e-4, "area {}", area);
+        assert_closed(&level.mesh);
+    }
+
+    #[test]
+    fn simplified_sphere_keeps_its_bounds() {
+        let sphere = MeshBuilder::sphere(1.0, 32, 16).build();
+        let level = sphere.simplify(200);
+        let (min, max) = level.mesh.bounding_box().unwrap();
+        for k in 0..3 {
+            assert!((min[k] + 1.0).abs() < 0.1, "min {:?}", min);
+            assert!((max[k] - 1.0).abs() < 0.1, "max {:?}", max);
+        }
+        assert_closed(&level.mesh);
+    }
+
+    #[test]
+    fn lod_chain_gets_coarser() {
+        let sphere = MeshBuilder::sphere(1.0, 32, 16).build();
+        let levels = sphere.lod_chain(&[0.1, 1.0, 0.5, 0.25]);
+        assert_eq!(levels.len(), 4);
+        assert_eq!(
+            levels[0].mesh.triangle_vertex_indices.len(),
+            sphere.triangle_vertex_indices.len()
+        );
+        assert_eq!(levels[0].geometric_error, 0.0);
+        for pair in levels.windows(2) {
+            assert!(
+                pair[1].mesh.triangle_vertex_indices.len()
+                    < pair[0].mesh.triangle_vertex_indices.len()
+            );
+            assert!(pair[1].geometric_error >= pair[0].geometric_error);
+        }
+    }
+
+    #[test]
+    fn chooses_coarser_levels_further_away() {
+        let sphere = MeshBuilder::sphere(1.0, 32, 16).build();
+        let levels = sphere.lod_chain(&[1.0, 0.5, 0.25, 0.1]);
+        let choose = |distance| choose_level_of_detail(&levels, distance, Deg(45.0), 1080, 1.0);
+
+        assert_eq!(choose(0.5), Some(0));
+        let mut previous = 0;
+        for &distance in &[1.0, 2.0, 5.0, 10.0, 50.0, 1000.0] {
+            let index = choose(distance).unwrap();
+            assert!(
+                index >= previous,
+                "level {} at distance {}",
+                index,
+                distance
+            );
+            previous = index;
+        }
+        assert_eq!(previous, 3);
+        assert_eq!(choose_level_of_detail(&[], 1.0, Deg(45.0), 1080, 1.0), None);
+    }
+
+    #[test]
+    fn screen_space_error_shrinks_with_distance() {
+        let level = LevelOfDetail {
+            mesh: Mesh::default(),
+            geometric_error: 0.01,
+        };
+        // At distance 1 with a 90 degree field of view, the viewport spans 2
+        // units.
+        let error = level.screen_space_error(1.0, Deg(90.0), 1000);
+        assert!((error - 5.0).abs() < 1e-3, "{}", error);
+        let error = level.screen_space_error(10.0, Deg(90.0), 1000);
+        assert!((error - 0.5).abs() < 1e-3, "{}", error);
+    }
+}
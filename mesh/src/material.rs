@@ -0,0 +1,146 @@
+use super::Mesh;
+use cgmath::Vector4;
+
+/// A material that is applied to some of a mesh's triangles, such as a group
+/// from an OBJ file or a material from a glTF file.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Material {
+    /// The name of the material or group.
+    pub name: String,
+
+    /// The linear RGBA base color of the material. Each component is in the
+    /// range `[0, 1]`. Renderers multiply it by the vertex colors.
+    pub base_color: Vector4<f32>,
+}
+
+impl Material {
+    pub fn new(name: &str, base_color: Vector4<f32>) -> Self {
+        Material {
+            name: name.to_string(),
+            base_color,
+        }
+    }
+}
+
+impl Default for Material {
+    /// Returns a white material named "default".
+    fn default() -> Self {
+        Material::new("default", Vector4::new(1.0, 1.0, 1.0, 1.0))
+    }
+}
+
+impl Mesh {
+    /// Returns the index into `materials` of the triangle's material, or
+    /// `None` if the mesh does not assign materials to triangles.
+    pub fn triangle_material(&self, triangle: usize) -> Option<usize> {
+        self.triangle_materials
+            .as_ref()
+            .map(|materials| materials[triangle])
+    }
+
+    /// Returns, for each material in `materials`, the indices of the
+    /// triangles that use it, in order. Returns a single group that contains
+    /// every triangle if the mesh does not assign materials to triangles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a triangle's material index is out of range. Meshes built
+    /// with [`MeshBuilder::materials`](super::MeshBuilder::materials) or read
+    /// with [`Mesh::read_binary`] are checked when they are created.
+    pub fn material_triangle_indices(&self) -> Vec<Vec<usize>> {
+        match &self.triangle_materials {
+            None => vec![(0..self.triangle_vertex_indices.len()).collect()],
+            Some(triangle_materials) => {
+                let mut groups = vec![vec![]; self.materials.len()];
+                for (t, &material) in triangle_materials.iter().enumerate() {
+                    groups[material].push(t);
+                }
+                groups
+            }
+        }
+    }
+
+    /// Creates a new mesh that contains only the triangles that use the given
+    /// material. The new mesh keeps the full list of materials, so material
+    /// indices stay the same.
+    pub fn material_submesh(&self, material: usize) -> Mesh {
+        let triangle_indices: Vec<usize> = (0..self.triangle_vertex_indices.len())
+            .filter(|&t| self.triangle_material(t) == Some(material))
+            .collect();
+        self.submesh(&triangle_indices)
+    }
+
+    /// Assigns every triangle in the mesh to a single material, replacing any
+    /// existing materials.
+    pub fn set_material(&mut self, material: Material) {
+        self.materials = vec![material];
+        self.triangle_materials = Some(vec![0; self.triangle_vertex_indices.len()]);
+    }
+
+    /// Returns the material of each triangle. If the mesh does not assign
+    /// materials to triangles yet, then every triangle is assigned to the
+    /// default material.
+    pub(crate) fn triangle_materials_or_default(&mut self) -> &mut Vec<usize> {
+        if self.triangle_materials.is_none() {
+            let triangle_count = self.triangle_vertex_indices.len();
+            let materials = if triangle_count > 0 {
+                vec![self.default_material_index(); triangle_count]
+            } else {
+                vec![]
+            };
+            self.triangle_materials = Some(materials);
+        }
+        self.triangle_materials.as_mut().unwrap()
+    }
+
+    /// Returns the index of a material that is equal to the default material,
+    /// adding one if there isn't one already.
+    pub(crate) fn default_material_index(&mut self) -> usize {
+        let default = Material::default();
+        match self.materials.iter().position(|m| *m == default) {
+            Some(index) => index,
+            None => {
+                self.materials.push(default);
+                self.materials.len() - 1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+    use cgmath::Vector3;
+
+    fn two_materials() -> Vec<Material> {
+        vec![
+            Material::default(),
+            Material::new("red", Vector4::new(1.0, 0.0, 0.0, 1.0)),
+        ]
+    }
+
+    #[test]
+    fn groups_triangles_by_material() {
+        let mesh = MeshBuilder::plane(1.0, 1.0, 2, 1)
+            .materials(two_materials(), vec![1, 0, 0, 1])
+            .build();
+        assert_eq!(
+            mesh.material_triangle_indices(),
+            vec![vec![1, 2], vec![0, 3]]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a material index for each triangle")]
+    fn builder_rejects_too_few_triangle_materials() {
+        MeshBuilder::cuboid(Vector3::new(1.0, 1.0, 1.0), 1).materials(two_materials(), vec![0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "material index 2 is out of range for 2 materials")]
+    fn builder_rejects_out_of_range_material() {
+        MeshBuilder::plane(1.0, 1.0, 1, 1).materials(two_materials(), vec![0, 2]);
+    }
+}
@@ -0,0 +1,249 @@
+use super::Mesh;
+use cgmath::{Point2, Vector4};
+
+impl Mesh {
+    /// Appends the vertices and triangles of `other` to this mesh. The indices
+    /// of the appended triangles are offset so that they refer to the appended
+    /// vertices.
+    ///
+    /// If only one of the two meshes contains an optional attribute, then the
+    /// vertices of the other mesh are given a default value for it: UV
+    /// coordinates of `(0, 0)`, tangents of `(1, 0, 0, 1)`, white colors, and
+    /// zero for custom attributes. Custom attributes with the same name but
+    /// different types keep the type from this mesh, and the vertices of
+    /// `other` are given zero.
+    ///
+    /// The materials of `other` are added after the materials of this mesh. If
+    /// only one of the two meshes assigns materials to its triangles, then the
+    /// triangles of the other mesh are assigned to a white material named
+    /// "default".
+    pub fn append(&mut self, other: &Mesh) {
+        let offset = self.positions.len();
+        let other_len = other.positions.len();
+
+        append_attribute(
+            &mut self.uvs,
+            &other.uvs,
+            offset,
+            other_len,
+            Point2::new(0.0, 0.0),
+        );
+        append_attribute(
+            &mut self.tangents,
+            &other.tangents,
+            offset,
+            other_len,
+            Vector4::new(1.0, 0.0, 0.0, 1.0),
+        );
+        append_attribute(
+            &mut self.colors,
+            &other.colors,
+            offset,
+            other_len,
+            Vector4::new(1.0, 1.0, 1.0, 1.0),
+        );
+
+        for (name, data) in &other.attributes {
+            self.attributes
+                .entry(name.clone())
+                .or_insert_with(|| data.defaults_like(offset));
+        }
+        for (name, data) in &mut self.attributes {
+            data.extend_from(other.attributes.get(name), other_len);
+        }
+
+        if self.triangle_materials.is_some() || other.triangle_materials.is_some() {
+            self.triangle_materials_or_default();
+            let material_offset = self.materials.len();
+            self.materials.extend(other.materials.iter().cloned());
+            let other_triangle_materials = match &other.triangle_materials {
+                Some(materials) => materials.iter().map(|m| m + material_offset).collect(),
+                None if other.triangle_vertex_indices.is_empty() => vec![],
+                None => vec![self.default_material_index(); other.triangle_vertex_indices.len()],
+            };
+            self.triangle_materials_or_default()
+                .extend(other_triangle_materials);
+        }
+
+        self.positions.extend_from_slice(&other.positions);
+        self.normals.extend_from_slice(&other.normals);
+        self.triangle_vertex_indices.extend(
+            other
+                .triangle_vertex_indices
+                .iter()
+                .map(|&(i1, i2, i3)| (i1 + offset, i2 + offset, i3 + offset)),
+        );
+    }
+
+    /// Combines the meshes into a single mesh. See [`Mesh::append`] for how
+    /// indices and optional attributes are handled.
+    pub fn merge<'a, I>(meshes: I) -> Mesh
+    where
+        I: IntoIterator<Item = &'a Mesh>,
+    {
+        let mut merged = Mesh::default();
+        for mesh in meshes {
+            merged.append(mesh);
+        }
+        merged
+    }
+}
+
+/// Appends the values of an optional per-vertex attribute of a mesh with
+/// `other_len` vertices to the attribute of a mesh with `len` vertices. Missing
+/// values on either side are filled with `default`.
+fn append_attribute<T: Clone>(
+    attribute: &mut Option<Vec<T>>,
+    other_attribute: &Option<Vec<T>>,
+    len: usize,
+    other_len: usize,
+    default: T,
+) {
+    *attribute = match (attribute.take(), other_attribute) {
+        (None, None) => None,
+        (Some(mut values), Some(other_values)) => {
+            values.extend_from_slice(other_values);
+            Some(values)
+        }
+        (Some(mut values), None) => {
+            values.resize(len + other_len, default);
+            Some(values)
+        }
+        (None, Some(other_values)) => {
+            let mut values = vec![default; len];
+            values.extend_from_slice(other_values);
+            Some(values)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttributeData, Material, MeshBuilder};
+    use cgmath::{Point3, Vector3};
+
+    /// Returns a triangle without any optional attributes, moved along X.
+    fn plain_triangle(x: f32) -> Mesh {
+        MeshBuilder::new(
+            vec![
+                Point3::new(x, 0.0, 0.0),
+                Point3::new(x + 1.0, 0.0, 0.0),
+                Point3::new(x, 1.0, 0.0),
+            ],
+            vec![Vector3::new(0.0, 0.0, 1.0); 3],
+            vec![(0, 1, 2)],
+        )
+        .build()
+    }
+
+    /// Returns a triangle with every optional attribute.
+    fn full_triangle() -> Mesh {
+        let red = Vector4::new(1.0, 0.0, 0.0, 1.0);
+        MeshBuilder::new(
+            plain_triangle(0.0).positions,
+            vec![Vector3::new(0.0, 0.0, 1.0); 3],
+            vec![(0, 1, 2)],
+        )
+        .uvs(vec![Point2::new(0.5, 0.5); 3])
+        .tangents(vec![Vector4::new(0.0, 1.0, 0.0, -1.0); 3])
+        .colors(vec![red; 3])
+        .attribute("stress", AttributeData::Float(vec![1.0, 2.0, 3.0]))
+        .attribute("part", AttributeData::Int(vec![7; 3]))
+        .materials(vec![Material::new("red", red)], vec![0])
+        .build()
+    }
+
+    #[test]
+    fn appending_fills_in_missing_attributes() {
+        let mut mesh = full_triangle();
+        mesh.append(&plain_triangle(2.0));
+
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.normals.len(), 6);
+        assert_eq!(mesh.triangle_vertex_indices, vec![(0, 1, 2), (3, 4, 5)]);
+        assert_eq!(mesh.positions[3], Point3::new(2.0, 0.0, 0.0));
+
+        let uvs = mesh.uvs.as_ref().unwrap();
+        assert_eq!(&uvs[3..], &[Point2::new(0.0, 0.0); 3]);
+        let tangents = mesh.tangents.as_ref().unwrap();
+        assert_eq!(&tangents[3..], &[Vector4::new(1.0, 0.0, 0.0, 1.0); 3]);
+        let colors = mesh.colors.as_ref().unwrap();
+        assert_eq!(&colors[3..], &[Vector4::new(1.0, 1.0, 1.0, 1.0); 3]);
+        assert_eq!(
+            mesh.attributes["stress"],
+            AttributeData::Float(vec![1.0, 2.0, 3.0, 0.0, 0.0, 0.0])
+        );
+        assert_eq!(
+            mesh.attributes["part"],
+            AttributeData::Int(vec![7, 7, 7, 0, 0, 0])
+        );
+
+        // The plain triangle gets the default material.
+        assert_eq!(
+            mesh.materials,
+            vec![full_triangle().materials[0].clone(), Material::default()]
+        );
+        assert_eq!(mesh.triangle_materials, Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn appending_to_a_plain_mesh_fills_in_its_attributes() {
+        let mut mesh = plain_triangle(2.0);
+        mesh.append(&full_triangle());
+
+        assert_eq!(mesh.triangle_vertex_indices, vec![(0, 1, 2), (3, 4, 5)]);
+        let colors = mesh.colors.as_ref().unwrap();
+        assert_eq!(&colors[..3], &[Vector4::new(1.0, 1.0, 1.0, 1.0); 3]);
+        assert_eq!(&colors[3..], &full_triangle().colors.unwrap()[..]);
+        assert_eq!(
+            mesh.attributes["stress"],
+            AttributeData::Float(vec![0.0, 0.0, 0.0, 1.0, 2.0, 3.0])
+        );
+        assert_eq!(
+            mesh.materials[mesh.triangle_materials.as_ref().unwrap()[0]],
+            Material::default()
+        );
+        assert_eq!(
+            mesh.materials[mesh.triangle_materials.as_ref().unwrap()[1]].name,
+            "red"
+        );
+    }
+
+    #[test]
+    fn mismatched_attribute_types_keep_this_meshs_type() {
+        let mut mesh = plain_triangle(0.0);
+        mesh.attributes
+            .insert("id".to_string(), AttributeData::Int(vec![1, 2, 3]));
+        let mut other = plain_triangle(2.0);
+        other
+            .attributes
+            .insert("id".to_string(), AttributeData::Float(vec![0.5; 3]));
+
+        mesh.append(&other);
+        assert_eq!(
+            mesh.attributes["id"],
+            AttributeData::Int(vec![1, 2, 3, 0, 0, 0])
+        );
+    }
+
+    #[test]
+    fn merge_offsets_each_mesh() {
+        let meshes = [
+            plain_triangle(0.0),
+            plain_triangle(2.0),
+            plain_triangle(4.0),
+        ];
+        let merged = Mesh::merge(&meshes);
+        assert_eq!(
+            merged.triangle_vertex_indices,
+            vec![(0, 1, 2), (3, 4, 5), (6, 7, 8)]
+        );
+        assert_eq!(merged.positions[6], Point3::new(4.0, 0.0, 0.0));
+        assert!(merged.uvs.is_none() && merged.colors.is_none());
+        assert!(merged.triangle_materials.is_none());
+
+        let empty = Mesh::merge(std::iter::empty());
+        assert!(empty.positions.is_empty());
+    }
+}
@@ -0,0 +1,385 @@
+use super::point_cloud::srgb_to_linear;
+use super::{Error, PointCloud, Result};
+use cgmath::{Point3, Vector3, Vector4};
+use std::io::BufRead;
+
+// A PLY file starts with a text header that declares a sequence of elements,
+// each with a count and a list of properties, and ends with `end_header`. The
+// body contains each instance of each element in order, in ASCII or in little-
+// or big-endian binary. Only the vertex element is read, but every element
+// before it has to be parsed to find where the vertices start.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarType {
+    Int8,
+    Uint8,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "char" | "int8" => Ok(ScalarType::Int8),
+            "uchar" | "uint8" => Ok(ScalarType::Uint8),
+            "short" | "int16" => Ok(ScalarType::Int16),
+            "ushort" | "uint16" => Ok(ScalarType::Uint16),
+            "int" | "int32" => Ok(ScalarType::Int32),
+            "uint" | "uint32" => Ok(ScalarType::Uint32),
+            "float" | "float32" => Ok(ScalarType::Float32),
+            "double" | "float64" => Ok(ScalarType::Float64),
+            _ => Err(Error::MalformedPly),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::Uint8 => 1,
+            ScalarType::Int16 | ScalarType::Uint16 => 2,
+            ScalarType::Int32 | ScalarType::Uint32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, ScalarType::Float32 | ScalarType::Float64)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Property {
+    Scalar(String, ScalarType),
+    List(ScalarType, ScalarType),
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl PointCloud {
+    /// Reads a point cloud from the vertices of a PLY file in ASCII or binary
+    /// format. Any other elements, such as faces, are ignored.
+    ///
+    /// Positions come from the `x`, `y`, and `z` properties. Normals come from
+    /// the `nx`, `ny`, and `nz` properties and colors come from the `red`,
+    /// `green`, `blue`, and optional `alpha` properties, if they are present.
+    /// Integer colors are 8-bit sRGB values and are converted to linear colors.
+    /// Floating-point colors are assumed to already be linear.
+    pub fn from_ply<R: BufRead>(reader: &mut R) -> Result<PointCloud> {
+        let (format, elements) = read_header(reader)?;
+        let mut values = match format {
+            Format::Ascii => ValueReader::Ascii(reader, String::new(), 0),
+            Format::BinaryLittleEndian | Format::BinaryBigEndian => {
+                let mut body = vec![];
+                reader.read_to_end(&mut body)?;
+                ValueReader::Binary(body, 0, format == Format::BinaryBigEndian)
+            }
+        };
+
+        for element in &elements {
+            if element.name != "vertex" {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        values.skip_property(property)?;
+                    }
+                }
+                continue;
+            }
+
+            let scalar = |name: &str| {
+                element
+                    .properties
+                    .iter()
+                    .position(|property| match property {
+                        Property::Scalar(n, _) => n == name,
+                        Property::List(..) => false,
+                    })
+            };
+            let all = |names: [&str; 3]| -> Option<[usize; 3]> {
+                Some([scalar(names[0])?, scalar(names[1])?, scalar(names[2])?])
+            };
+            let position_properties = all(["x", "y", "z"]).ok_or(Error::MalformedPly)?;
+            let normal_properties = all(["nx", "ny", "nz"]);
+            let color_properties = all(["red", "green", "blue"]);
+            let alpha_property = scalar("alpha");
+
+            // The count comes from the header, so it isn't trusted enough to
+            // allocate for.
+            let mut positions = vec![];
+            let mut normals = vec![];
+            let mut colors = vec![];
+            let mut row = vec![0.0f64; element.properties.len()];
+            for _ in 0..element.count {
+                for (value, property) in row.iter_mut().zip(&element.properties) {
+                    *value = match property {
+                        Property::Scalar(_, ty) => values.read(*ty)?,
+                        Property::List(..) => {
+                            values.skip_property(property)?;
+                            0.0
+                        }
+                    };
+                }
+
+                let [x, y, z] = position_properties.map(|p| row[p] as f32);
+                positions.push(Point3::new(x, y, z));
+                if let Some(properties) = normal_properties {
+                    let [x, y, z] = properties.map(|p| row[p] as f32);
+                    normals.push(Vector3::new(x, y, z));
+                }
+                if let Some(properties) = color_properties {
+                    let component = |p: usize, is_srgb: bool| match &element.properties[p] {
+                        Property::Scalar(_, ty) if ty.is_integer() => {
+                            let value = (row[p] / 255.0) as f32;
+                            if is_srgb {
+                                srgb_to_linear(value)
+                            } else {
+                                value
+                            }
+                        }
+                        _ => row[p] as f32,
+                    };
+                    let [r, g, b] = properties.map(|p| component(p, true));
+                    let a = alpha_property.map_or(1.0, |p| component(p, false));
+                    colors.push(Vector4::new(r, g, b, a));
+                }
+            }
+
+            let mut cloud = PointCloud::new(positions);
+            if normal_properties.is_some() {
+                cloud.normals = Some(normals);
+            }
+            if color_properties.is_some() {
+                cloud.colors = Some(colors);
+            }
+            return Ok(cloud);
+        }
+
+        Err(Error::MalformedPly)
+    }
+}
+
+/// Reads the header of a PLY file, leaving the reader at the start of the
+/// body.
+fn read_header<R: BufRead>(reader: &mut R) -> Result<(Format, Vec<Element>)> {
+    let mut line = String::new();
+    let mut next_line = |reader: &mut R| -> Result<String> {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::MalformedPly);
+        }
+        Ok(line.trim().to_string())
+    };
+
+    if next_line(reader)? != "ply" {
+        return Err(Error::NotPly);
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    loop {
+        let line = next_line(reader)?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(Error::MalformedPly),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| Error::MalformedPly)?,
+                properties: vec![],
+            }),
+            ["property", "list", count_type, item_type, _name] => elements
+                .last_mut()
+                .ok_or(Error::MalformedPly)?
+                .properties
+                .push(Property::List(
+                    ScalarType::parse(count_type)?,
+                    ScalarType::parse(item_type)?,
+                )),
+            ["property", ty, name] => elements
+                .last_mut()
+                .ok_or(Error::MalformedPly)?
+                .properties
+                .push(Property::Scalar(name.to_string(), ScalarType::parse(ty)?)),
+            _ => return Err(Error::MalformedPly),
+        }
+    }
+
+    Ok((format.ok_or(Error::MalformedPly)?, elements))
+}
+
+/// Reads the values in the body of a PLY file.
+enum ValueReader<'a, R> {
+    /// The reader of an ASCII body, which is read a line at a time, the
+    /// current line, and the index of the next whitespace-separated word in
+    /// it.
+    Ascii(&'a mut R, String, usize),
+
+    /// The bytes of a binary body, the offset of the next value, and whether
+    /// the values are big-endian.
+    Binary(Vec<u8>, usize, bool),
+}
+
+impl<'a, R: BufRead> ValueReader<'a, R> {
+    fn read(&mut self, ty: ScalarType) -> Result<f64> {
+        match self {
+            ValueReader::Ascii(reader, line, next) => loop {
+                if let Some(word) = line.split_whitespace().nth(*next) {
+                    *next += 1;
+                    return word.parse::<f64>().map_err(|_| Error::MalformedPly);
+                }
+                line.clear();
+                *next = 0;
+                if reader.read_line(line)? == 0 {
+                    return Err(Error::MalformedPly);
+                }
+            },
+            ValueReader::Binary(bytes, offset, is_big_endian) => {
+                let size = ty.size();
+                let mut value = bytes
+                    .get(*offset..*offset + size)
+                    .ok_or(Error::MalformedPly)?
+                    .to_vec();
+                *offset += size;
+                if *is_big_endian {
+                    value.reverse();
+                }
+                let mut word = [0u8; 8];
+                word[..size].copy_from_slice(&value);
+                Ok(match ty {
+                    ScalarType::Int8 => i8::from_le_bytes([word[0]]) as f64,
+                    ScalarType::Uint8 => word[0] as f64,
+                    ScalarType::Int16 => i16::from_le_bytes([word[0], word[1]]) as f64,
+                    ScalarType::Uint16 => u16::from_le_bytes([word[0], word[1]]) as f64,
+                    ScalarType::Int32 => {
+                        i32::from_le_bytes([word[0], word[1], word[2], word[3]]) as f64
+                    }
+                    ScalarType::Uint32 => {
+                        u32::from_le_bytes([word[0], word[1], word[2], word[3]]) as f64
+                    }
+                    ScalarType::Float32 => {
+                        f32::from_le_bytes([word[0], word[1], word[2], word[3]]) as f64
+                    }
+                    ScalarType::Float64 => f64::from_le_bytes(word),
+                })
+            }
+        }
+    }
+
+    fn skip_property(&mut self, property: &Property) -> Result<()> {
+        match property {
+            Property::Scalar(_, ty) => {
+                self.read(*ty)?;
+            }
+            Property::List(count_type, item_type) => {
+                let count = self.read(*count_type)?;
+                if count < 0.0 {
+                    return Err(Error::MalformedPly);
+                }
+                for _ in 0..count as usize {
+                    self.read(*item_type)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_ascii_vertices_after_other_elements() {
+        let ply = "ply\n\
+            format ascii 1.0\n\
+            element face 1\n\
+            property list uchar int vertex_indices\n\
+            element vertex 2\n\
+            property float x\n\
+            property float y\n\
+            property float z\n\
+            property uchar red\n\
+            property uchar green\n\
+            property uchar blue\n\
+            end_header\n\
+            3 0 1\n\
+            2\n\
+            1 2 3 255 0 0\n\
+            -1.5 0 2e1\n\
+            0 255 0\n";
+        let cloud = PointCloud::from_ply(&mut ply.as_bytes()).unwrap();
+        assert_eq!(
+            cloud.positions,
+            vec![Point3::new(1.0, 2.0, 3.0), Point3::new(-1.5, 0.0, 20.0)]
+        );
+        assert!(cloud.normals.is_none());
+        assert_eq!(
+            cloud.colors,
+            Some(vec![
+                Vector4::new(1.0, 0.0, 0.0, 1.0),
+                Vector4::new(0.0, 1.0, 0.0, 1.0)
+            ])
+        );
+    }
+
+    #[test]
+    fn reads_big_endian_binary_vertices() {
+        let mut ply = b"ply\n\
+            format binary_big_endian 1.0\n\
+            element vertex 1\n\
+            property short x\n\
+            property double y\n\
+            property float z\n\
+            end_header\n"
+            .to_vec();
+        ply.extend_from_slice(&(-2i16).to_be_bytes());
+        ply.extend_from_slice(&0.5f64.to_be_bytes());
+        ply.extend_from_slice(&4.0f32.to_be_bytes());
+        let cloud = PointCloud::from_ply(&mut ply.as_slice()).unwrap();
+        assert_eq!(cloud.positions, vec![Point3::new(-2.0, 0.5, 4.0)]);
+    }
+
+    #[test]
+    fn rejects_huge_vertex_count() {
+        let ply = "ply\nformat binary_little_endian 1.0\n\
+            element vertex 18446744073709551615\n\
+            property float x\nproperty float y\nproperty float z\nend_header\n";
+        assert!(matches!(
+            PointCloud::from_ply(&mut ply.as_bytes()),
+            Err(Error::MalformedPly)
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_ascii_body() {
+        let ply = "ply\nformat ascii 1.0\nelement vertex 2\n\
+            property float x\nproperty float y\nproperty float z\nend_header\n\
+            0 0 0\n1 1\n";
+        assert!(matches!(
+            PointCloud::from_ply(&mut ply.as_bytes()),
+            Err(Error::MalformedPly)
+        ));
+    }
+}
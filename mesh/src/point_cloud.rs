@@ -0,0 +1,211 @@
+use super::{normal_matrix, Error, Result};
+use cgmath::{InnerSpace, Matrix4, Point3, Transform, Vector3, Vector4};
+use std::io::BufRead;
+
+/// A set of points without any connectivity, such as a raw scan.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointCloud {
+    /// Contains a position for each point in the cloud.
+    pub positions: Vec<Point3<f32>>,
+
+    /// Contains a normal vector for each point in the cloud.
+    pub normals: Option<Vec<Vector3<f32>>>,
+
+    /// Contains a linear RGBA color for each point in the cloud. Each
+    /// component is in the range `[0, 1]`.
+    pub colors: Option<Vec<Vector4<f32>>>,
+}
+
+impl PointCloud {
+    pub fn new(positions: Vec<Point3<f32>>) -> Self {
+        PointCloud {
+            positions,
+            normals: None,
+            colors: None,
+        }
+    }
+
+    pub fn normals(mut self, normals: Vec<Vector3<f32>>) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn colors(mut self, colors: Vec<Vector4<f32>>) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Reads a point cloud from an XYZ file. Each line of the file contains
+    /// the coordinates of one point, optionally followed by its normal and
+    /// then by its color:
+    ///
+    /// - 3 values: `x y z`
+    /// - 6 values: `x y z nx ny nz`
+    /// - 9 values: `x y z nx ny nz r g b`
+    ///
+    /// Colors are 8-bit sRGB values in the range `[0, 255]` and are converted
+    /// to linear colors. Values may be separated by whitespace or commas.
+    /// Blank lines and lines that start with `#` are skipped. Every point must
+    /// have the same number of values.
+    pub fn from_xyz<R: BufRead>(reader: &mut R) -> Result<PointCloud> {
+        let mut positions = vec![];
+        let mut normals = vec![];
+        let mut colors = vec![];
+        let mut column_count = None;
+
+        for (line_index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let malformed = || Error::MalformedXyz(line_index + 1);
+            let values = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|value| !value.is_empty())
+                .map(|value| value.parse::<f32>().map_err(|_| malformed()))
+                .collect::<Result<Vec<f32>>>()?;
+            if !matches!(values.len(), 3 | 6 | 9)
+                || *column_count.get_or_insert(values.len()) != values.len()
+            {
+                return Err(malformed());
+            }
+
+            positions.push(Point3::new(values[0], values[1], values[2]));
+            if values.len() >= 6 {
+                normals.push(Vector3::new(values[3], values[4], values[5]));
+            }
+            if values.len() == 9 {
+                colors.push(Vector4::new(
+                    srgb_to_linear(values[6] / 255.0),
+                    srgb_to_linear(values[7] / 255.0),
+                    srgb_to_linear(values[8] / 255.0),
+                    1.0,
+                ));
+            }
+        }
+
+        let mut cloud = PointCloud::new(positions);
+        if column_count.unwrap_or(0) >= 6 {
+            cloud.normals = Some(normals);
+        }
+        if column_count == Some(9) {
+            cloud.colors = Some(colors);
+        }
+        Ok(cloud)
+    }
+
+    /// Adds the points of another cloud to this one. If only one of the clouds
+    /// has normals, then the other cloud's points get zero normals. If only one
+    /// of the clouds has colors, then the other cloud's points are white.
+    pub fn append(&mut self, other: &PointCloud) {
+        let count = self.positions.len();
+        let other_count = other.positions.len();
+
+        if self.normals.is_some() || other.normals.is_some() {
+            let zero = Vector3::new(0.0, 0.0, 0.0);
+            let mut normals = self.normals.take().unwrap_or_else(|| vec![zero; count]);
+            match &other.normals {
+                Some(other_normals) => normals.extend_from_slice(other_normals),
+                None => normals.resize(count + other_count, zero),
+            }
+            self.normals = Some(normals);
+        }
+
+        if self.colors.is_some() || other.colors.is_some() {
+            let white = Vector4::new(1.0, 1.0, 1.0, 1.0);
+            let mut colors = self.colors.take().unwrap_or_else(|| vec![white; count]);
+            match &other.colors {
+                Some(other_colors) => colors.extend_from_slice(other_colors),
+                None => colors.resize(count + other_count, white),
+            }
+            self.colors = Some(colors);
+        }
+
+        self.positions.extend_from_slice(&other.positions);
+    }
+
+    /// Apply the transformation matrix to the position and normal of each
+    /// point in the cloud. Normals are transformed by the inverse transpose of
+    /// the matrix, as in `Mesh::transform`.
+    pub fn transform(&mut self, transformation: Matrix4<f32>) {
+        for p in &mut self.positions {
+            *p = transformation.transform_point(*p);
+        }
+
+        if let Some(normals) = &mut self.normals {
+            let normal_matrix = normal_matrix(transformation);
+            for n in normals {
+                // Zero normals mark points without a known normal, so they
+                // are left as they are.
+                if n.magnitude2() > 0.0 {
+                    *n = (normal_matrix * *n).normalize();
+                }
+            }
+        }
+    }
+
+    /// Moves the cloud by the given offset.
+    pub fn translate(&mut self, offset: Vector3<f32>) {
+        for p in &mut self.positions {
+            *p += offset;
+        }
+    }
+
+    /// Returns the minimum and maximum corners of an axis-aligned bounding box
+    /// around the cloud, or `None` if the cloud is empty.
+    pub fn bounding_box(&self) -> Option<(Point3<f32>, Point3<f32>)> {
+        let first = *self.positions.first()?;
+        Some(self.positions.iter().fold((first, first), |(min, max), p| {
+            (
+                Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        }))
+    }
+}
+
+/// Converts an sRGB encoded color component in the range `[0, 1]` to linear.
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+
+    #[test]
+    fn transforms_normals_like_meshes() {
+        let mesh = MeshBuilder::sphere(1.0, 8, 4).build();
+        let mut cloud = PointCloud::new(mesh.positions.clone()).normals(mesh.normals.clone());
+        let mut mesh = mesh;
+
+        // A shear combined with a mirror.
+        let mut transformation = Matrix4::from_nonuniform_scale(-2.0, 1.0, 0.5);
+        transformation.y.x = 0.7;
+        mesh.transform(transformation);
+        cloud.transform(transformation);
+
+        assert_eq!(cloud.positions, mesh.positions);
+        assert_eq!(cloud.normals.as_ref(), Some(&mesh.normals));
+        // The normals still point away from the transformed sphere's center.
+        for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+            assert!(n.dot(p - Point3::new(0.0, 0.0, 0.0)) > 0.0);
+        }
+    }
+
+    #[test]
+    fn keeps_zero_normals() {
+        let mut cloud = PointCloud::new(vec![Point3::new(1.0, 2.0, 3.0)])
+            .normals(vec![Vector3::new(0.0, 0.0, 0.0)]);
+        cloud.transform(Matrix4::from_scale(2.0));
+        assert_eq!(cloud.normals, Some(vec![Vector3::new(0.0, 0.0, 0.0)]));
+    }
+}
@@ -0,0 +1,465 @@
+use super::MeshBuilder;
+use cgmath::{InnerSpace, Point2, Point3, Vector3};
+use std::f32::consts::PI;
+
+// All primitives are centered at the origin with Z as the up axis. Triangles
+// are wound counterclockwise when viewed from outside the primitive.
+
+impl MeshBuilder {
+    /// Creates a box with the given side lengths along the X, Y, and Z axes.
+    /// Each face is split into a grid of `subdivisions` by `subdivisions`
+    /// quads, and each face has its own vertices so that edges stay sharp. Each
+    /// face is mapped to the full `[0, 1]` UV range.
+    pub fn cuboid(size: Vector3<f32>, subdivisions: usize) -> MeshBuilder {
+        let h = size / 2.0;
+        let n = subdivisions.max(1);
+        let mut data = PrimitiveData::default();
+
+        #[rustfmt::skip]
+        let faces = [
+            (Point3::new(h.x, -h.y, -h.z), Vector3::new(0.0, size.y, 0.0), Vector3::new(0.0, 0.0, size.z)),
+            (Point3::new(-h.x, h.y, -h.z), Vector3::new(0.0, -size.y, 0.0), Vector3::new(0.0, 0.0, size.z)),
+            (Point3::new(h.x, h.y, -h.z), Vector3::new(-size.x, 0.0, 0.0), Vector3::new(0.0, 0.0, size.z)),
+            (Point3::new(-h.x, -h.y, -h.z), Vector3::new(size.x, 0.0, 0.0), Vector3::new(0.0, 0.0, size.z)),
+            (Point3::new(-h.x, -h.y, h.z), Vector3::new(size.x, 0.0, 0.0), Vector3::new(0.0, size.y, 0.0)),
+            (Point3::new(-h.x, h.y, -h.z), Vector3::new(size.x, 0.0, 0.0), Vector3::new(0.0, -size.y, 0.0)),
+        ];
+        for &(origin, u, v) in &faces {
+            data.push_grid(origin, u, v, n, n);
+        }
+
+        data.into_builder()
+    }
+
+    /// Creates a flat rectangle in the XY plane that faces the +Z axis. The
+    /// rectangle is split into a grid of `x_subdivisions` by `y_subdivisions`
+    /// quads and is mapped to the full `[0, 1]` UV range.
+    pub fn plane(
+        width: f32,
+        depth: f32,
+        x_subdivisions: usize,
+        y_subdivisions: usize,
+    ) -> MeshBuilder {
+        let mut data = PrimitiveData::default();
+        data.push_grid(
+            Point3::new(-width / 2.0, -depth / 2.0, 0.0),
+            Vector3::new(width, 0.0, 0.0),
+            Vector3::new(0.0, depth, 0.0),
+            x_subdivisions.max(1),
+            y_subdivisions.max(1),
+        );
+        data.into_builder()
+    }
+
+    /// Creates a UV sphere with its poles on the Z axis. `segments` is the
+    /// number of divisions around the Z axis and must be at least 3. `rings` is
+    /// the number of divisions from pole to pole and must be at least 2.
+    /// Smaller values are raised to the minimum.
+    ///
+    /// U increases around the Z axis and V increases from the +Z pole to the
+    /// -Z pole.
+    pub fn sphere(radius: f32, segments: usize, rings: usize) -> MeshBuilder {
+        let segments = segments.max(3);
+        let rings = rings.max(2);
+        let mut data = PrimitiveData::default();
+
+        // The first and last column of vertices are at the same positions but
+        // have different UV coordinates.
+        for ring in 0..=rings {
+            let v = ring as f32 / rings as f32;
+            // The poles are set exactly so that their vertices coincide.
+            let (sin_theta, cos_theta) = match ring {
+                0 => (0.0, 1.0),
+                r if r == rings => (0.0, -1.0),
+                _ => (v * PI).sin_cos(),
+            };
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let (sin_phi, cos_phi) = turn_sin_cos(u);
+                let normal = Vector3::new(sin_theta * cos_phi, sin_theta * sin_phi, cos_theta);
+                data.push_vertex(Point3::new(0.0, 0.0, 0.0) + normal * radius, normal, u, v);
+            }
+        }
+
+        let columns = segments + 1;
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let a = ring * columns + segment;
+                let b = a + columns;
+                let c = b + 1;
+                let d = a + 1;
+
+                // Skip the triangles that would collapse into the poles.
+                if ring != 0 {
+                    data.push_triangle(a, b, d);
+                }
+                if ring != rings - 1 {
+                    data.push_triangle(b, c, d);
+                }
+            }
+        }
+
+        data.into_builder()
+    }
+
+    /// Creates a cylinder along the Z axis with closed caps. `segments` is the
+    /// number of divisions around the Z axis and must be at least 3. Smaller
+    /// values are raised to the minimum.
+    ///
+    /// The side is mapped to the full `[0, 1]` UV range, and each cap is mapped
+    /// to a disk inscribed in the `[0, 1]` UV range.
+    pub fn cylinder(radius: f32, height: f32, segments: usize) -> MeshBuilder {
+        let segments = segments.max(3);
+        let h = height / 2.0;
+        let mut data = PrimitiveData::default();
+
+        let start = data.positions.len();
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let (sin, cos) = turn_sin_cos(u);
+            let normal = Vector3::new(cos, sin, 0.0);
+            let x = cos * radius;
+            let y = sin * radius;
+            data.push_vertex(Point3::new(x, y, -h), normal, u, 0.0);
+            data.push_vertex(Point3::new(x, y, h), normal, u, 1.0);
+        }
+        for segment in 0..segments {
+            let a = start + 2 * segment;
+            data.push_quad(a, a + 2, a + 3, a + 1);
+        }
+
+        data.push_cap(radius, h, segments, true);
+        data.push_cap(radius, -h, segments, false);
+        data.into_builder()
+    }
+
+    /// Creates a cone along the Z axis with its apex on +Z and a closed base.
+    /// `segments` is the number of divisions around the Z axis and must be at
+    /// least 3. Smaller values are raised to the minimum.
+    ///
+    /// The side is mapped to the full `[0, 1]` UV range, and the base is mapped
+    /// to a disk inscribed in the `[0, 1]` UV range.
+    pub fn cone(radius: f32, height: f32, segments: usize) -> MeshBuilder {
+        let segments = segments.max(3);
+        let h = height / 2.0;
+        let mut data = PrimitiveData::default();
+
+        // The side normal tilts up from the XY plane by the cone's half angle.
+        let slope = Vector3::new(height, 0.0, radius).normalize();
+        let side_normal = |angle: f32| {
+            let (sin, cos) = angle.sin_cos();
+            Vector3::new(cos * slope.x, sin * slope.x, slope.z)
+        };
+
+        let start = data.positions.len();
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let angle = u * 2.0 * PI;
+            let (sin, cos) = turn_sin_cos(u);
+            data.push_vertex(
+                Point3::new(cos * radius, sin * radius, -h),
+                side_normal(angle),
+                u,
+                0.0,
+            );
+        }
+        // Each side triangle gets its own apex vertex so that the normal at the
+        // apex points halfway between the normals at the base.
+        for segment in 0..segments {
+            let u = (segment as f32 + 0.5) / segments as f32;
+            let apex =
+                data.push_vertex(Point3::new(0.0, 0.0, h), side_normal(u * 2.0 * PI), u, 1.0);
+            data.push_triangle(start + segment, start + segment + 1, apex);
+        }
+
+        data.push_cap(radius, -h, segments, false);
+        data.into_builder()
+    }
+
+    /// Creates a torus that lies in the XY plane. `major_radius` is the
+    /// distance from the origin to the center of the tube, and `minor_radius`
+    /// is the radius of the tube. `major_segments` is the number of divisions
+    /// around the Z axis and `minor_segments` is the number of divisions around
+    /// the tube. Both must be at least 3. Smaller values are raised to the
+    /// minimum.
+    pub fn torus(
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: usize,
+        minor_segments: usize,
+    ) -> MeshBuilder {
+        let major_segments = major_segments.max(3);
+        let minor_segments = minor_segments.max(3);
+        let mut data = PrimitiveData::default();
+
+        for i in 0..=major_segments {
+            let u = i as f32 / major_segments as f32;
+            let (sin_phi, cos_phi) = turn_sin_cos(u);
+            for j in 0..=minor_segments {
+                let v = j as f32 / minor_segments as f32;
+                let (sin_theta, cos_theta) = turn_sin_cos(v);
+                let normal = Vector3::new(cos_theta * cos_phi, cos_theta * sin_phi, sin_theta);
+                let center = Point3::new(cos_phi * major_radius, sin_phi * major_radius, 0.0);
+                data.push_vertex(center + normal * minor_radius, normal, u, v);
+            }
+        }
+
+        let columns = minor_segments + 1;
+        for i in 0..major_segments {
+            for j in 0..minor_segments {
+                let a = i * columns + j;
+                data.push_quad(a, a + columns, a + columns + 1, a + 1);
+            }
+        }
+
+        data.into_builder()
+    }
+}
+
+/// Accumulates the vertices and triangles of a primitive.
+#[derive(Default)]
+pub(crate) struct PrimitiveData {
+    positions: Vec<Point3<f32>>,
+    normals: Vec<Vector3<f32>>,
+    uvs: Vec<Point2<f32>>,
+    triangle_vertex_indices: Vec<(usize, usize, usize)>,
+}
+
+impl PrimitiveData {
+    /// Adds a vertex and returns its index.
+    pub(crate) fn push_vertex(
+        &mut self,
+        position: Point3<f32>,
+        normal: Vector3<f32>,
+        u: f32,
+        v: f32,
+    ) -> usize {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.uvs.push(Point2::new(u, v));
+        self.positions.len() - 1
+    }
+
+    /// Returns the number of vertices that have been added.
+    pub(crate) fn positions_len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub(crate) fn push_triangle(&mut self, a: usize, b: usize, c: usize) {
+        self.triangle_vertex_indices.push((a, b, c));
+    }
+
+    /// Adds two triangles that cover the quad with the given corners in
+    /// counterclockwise order.
+    pub(crate) fn push_quad(&mut self, a: usize, b: usize, c: usize, d: usize) {
+        self.push_triangle(a, b, c);
+        self.push_triangle(a, c, d);
+    }
+
+    /// Adds a flat grid of quads that spans `u` and `v` from `origin`. The grid
+    /// faces the direction of `u × v`.
+    fn push_grid(
+        &mut self,
+        origin: Point3<f32>,
+        u: Vector3<f32>,
+        v: Vector3<f32>,
+        u_subdivisions: usize,
+        v_subdivisions: usize,
+    ) {
+        let normal = u.cross(v).normalize();
+        let start = self.positions.len();
+
+        for j in 0..=v_subdivisions {
+            let t = j as f32 / v_subdivisions as f32;
+            for i in 0..=u_subdivisions {
+                let s = i as f32 / u_subdivisions as f32;
+                self.push_vertex(origin + u * s + v * t, normal, s, t);
+            }
+        }
+
+        let columns = u_subdivisions + 1;
+        for j in 0..v_subdivisions {
+            for i in 0..u_subdivisions {
+                let a = start + j * columns + i;
+                self.push_quad(a, a + 1, a + columns + 1, a + columns);
+            }
+        }
+    }
+
+    /// Adds a disk at height `z` that faces +Z if `faces_up` is true and -Z
+    /// otherwise.
+    fn push_cap(&mut self, radius: f32, z: f32, segments: usize, faces_up: bool) {
+        let normal = Vector3::new(0.0, 0.0, if faces_up { 1.0 } else { -1.0 });
+        let center = self.push_vertex(Point3::new(0.0, 0.0, z), normal, 0.5, 0.5);
+
+        let start = self.positions.len();
+        for segment in 0..=segments {
+            let (sin, cos) = turn_sin_cos(segment as f32 / segments as f32);
+            self.push_vertex(
+                Point3::new(cos * radius, sin * radius, z),
+                normal,
+                0.5 + cos / 2.0,
+                0.5 + sin / 2.0,
+            );
+        }
+
+        for segment in 0..segments {
+            let a = start + segment;
+            if faces_up {
+                self.push_triangle(center, a, a + 1);
+            } else {
+                self.push_triangle(center, a + 1, a);
+            }
+        }
+    }
+
+    pub(crate) fn into_builder(self) -> MeshBuilder {
+        MeshBuilder::new(self.positions, self.normals, self.triangle_vertex_indices).uvs(self.uvs)
+    }
+}
+
+/// Returns the sine and cosine of the given fraction of a full turn. Whole
+/// turns give exactly `(0, 1)`, so the vertices on either side of a seam have
+/// exactly the same position.
+fn turn_sin_cos(fraction: f32) -> (f32, f32) {
+    (fraction.fract() * 2.0 * PI).sin_cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::assert_closed;
+    use crate::Mesh;
+    use cgmath::{EuclideanSpace, Point3};
+
+    /// Asserts that every normal has unit length and that every triangle is
+    /// wound counterclockwise when viewed from the side that `outward` points
+    /// to at the triangle's centroid.
+    fn assert_outward(mesh: &Mesh, outward: impl Fn(Point3<f32>) -> Vector3<f32>) {
+        for n in &mesh.normals {
+            assert!((n.magnitude() - 1.0).abs() < 1e-5, "normal {:?}", n);
+        }
+        for triangle in mesh.triangles_iter() {
+            let (p1, p2, p3) = triangle.positions();
+            let face_normal = (p2 - p1).cross(p3 - p1);
+            assert!(
+                face_normal.magnitude() > 0.0,
+                "degenerate triangle {}",
+                triangle.index()
+            );
+            assert!(
+                face_normal.dot(outward(triangle.centroid())) > 0.0,
+                "triangle {} faces inward",
+                triangle.index()
+            );
+            let (n1, n2, n3) = triangle.normals();
+            for n in [n1, n2, n3] {
+                assert!(
+                    n.dot(face_normal) > 0.0,
+                    "triangle {} disagrees with its normals",
+                    triangle.index()
+                );
+            }
+        }
+    }
+
+    /// Returns the volume enclosed by a closed mesh.
+    fn volume(mesh: &Mesh) -> f32 {
+        mesh.triangles_iter()
+            .map(|triangle| {
+                let (p1, p2, p3) = triangle.positions();
+                p1.to_vec().dot(p2.to_vec().cross(p3.to_vec())) / 6.0
+            })
+            .sum()
+    }
+
+    fn from_origin(p: Point3<f32>) -> Vector3<f32> {
+        p.to_vec()
+    }
+
+    #[test]
+    fn cuboid_faces_outward() {
+        assert_outward(
+            &MeshBuilder::cuboid(Vector3::new(1.0, 2.0, 3.0), 3).build(),
+            from_origin,
+        );
+    }
+
+    #[test]
+    fn cuboid_has_expected_area_volume_and_bounds() {
+        let mesh = MeshBuilder::cuboid(Vector3::new(1.0, 2.0, 3.0), 2).build();
+        let area: f32 = mesh.triangles_iter().map(|t| t.area()).sum();
+        assert!((area - 22.0).abs() < 1e-4, "area {}", area);
+        assert!(
+            (volume(&mesh) - 6.0).abs() < 1e-4,
+            "volume {}",
+            volume(&mesh)
+        );
+        assert_eq!(
+            mesh.bounding_box(),
+            Some((Point3::new(-0.5, -1.0, -1.5), Point3::new(0.5, 1.0, 1.5)))
+        );
+        assert_eq!(mesh.triangle_vertex_indices.len(), 6 * 2 * 2 * 2);
+    }
+
+    #[test]
+    fn plane_faces_up() {
+        assert_outward(&MeshBuilder::plane(2.0, 1.0, 3, 2).build(), |_| {
+            Vector3::unit_z()
+        });
+    }
+
+    #[test]
+    fn sphere_faces_outward() {
+        let mesh = MeshBuilder::sphere(2.0, 16, 8).build();
+        assert_outward(&mesh, from_origin);
+        for p in &mesh.positions {
+            assert!((p.to_vec().magnitude() - 2.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn cylinder_faces_outward() {
+        assert_outward(&MeshBuilder::cylinder(1.0, 2.0, 12).build(), from_origin);
+    }
+
+    #[test]
+    fn cone_faces_outward() {
+        assert_outward(&MeshBuilder::cone(1.0, 2.0, 12).build(), from_origin);
+    }
+
+    #[test]
+    fn torus_faces_outward() {
+        let major_radius = 2.0;
+        assert_outward(&MeshBuilder::torus(major_radius, 0.5, 16, 8).build(), |p| {
+            // Away from the closest point on the circle at the tube's center.
+            let center = Vector3::new(p.x, p.y, 0.0).normalize() * major_radius;
+            p.to_vec() - center
+        });
+    }
+
+    #[test]
+    fn sphere_has_exact_poles_and_closed_seam() {
+        let (segments, rings) = (7, 5);
+        let mesh = MeshBuilder::sphere(2.0, segments, rings).build();
+        let columns = segments + 1;
+        let last_ring = rings * columns;
+        for segment in 0..columns {
+            assert_eq!(mesh.positions[segment], Point3::new(0.0, 0.0, 2.0));
+            assert_eq!(
+                mesh.positions[last_ring + segment],
+                Point3::new(0.0, 0.0, -2.0)
+            );
+        }
+        for ring in 0..=rings {
+            let first = ring * columns;
+            assert_eq!(mesh.positions[first], mesh.positions[first + segments]);
+        }
+        assert_closed(&mesh);
+    }
+
+    #[test]
+    fn torus_has_closed_seams() {
+        assert_closed(&MeshBuilder::torus(2.0, 0.5, 9, 7).build());
+    }
+}
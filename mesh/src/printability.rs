@@ -0,0 +1,246 @@
+use super::bvh::Aabb;
+use super::Mesh;
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Rad, Vector3};
+
+/// Settings for [`Mesh::analyze_printability`]. Lengths are in the mesh's
+/// units, and the defaults assume millimeters and a typical FDM printer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrintabilitySettings {
+    /// The direction in which the printer stacks layers. It does not need to
+    /// be normalized.
+    pub build_direction: Vector3<f32>,
+
+    /// The largest overhang angle, measured from the build direction, that can
+    /// be printed without support.
+    pub max_overhang_angle: Rad<f32>,
+
+    /// The thinnest wall that can be printed reliably.
+    pub min_wall_thickness: f32,
+
+    /// The smallest size of a disconnected piece that can be printed reliably.
+    pub min_feature_size: f32,
+}
+
+impl Default for PrintabilitySettings {
+    fn default() -> Self {
+        PrintabilitySettings {
+            build_direction: Vector3::new(0.0, 0.0, 1.0),
+            max_overhang_angle: Deg(45.0).into(),
+            min_wall_thickness: 0.8,
+            min_feature_size: 2.0,
+        }
+    }
+}
+
+/// The results of [`Mesh::analyze_printability`]. Each vector has one element
+/// for each triangle in the mesh. Use [`Mesh::face_values_to_vertex_values`]
+/// and [`Mesh::set_colors_from_values`] to visualize them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintabilityReport {
+    /// The overhang angle of each triangle in radians. This is zero for
+    /// triangles that face up or sideways and increases to `π / 2` for
+    /// triangles that face straight down.
+    pub overhang_angles: Vec<f32>,
+
+    /// Whether each triangle overhangs by more than the maximum overhang angle
+    /// and does not rest on the build plate.
+    pub needs_support: Vec<bool>,
+
+    /// The total area of the triangles that need support.
+    pub unsupported_area: f32,
+
+    /// The thickness of the wall behind each triangle, measured by casting a
+    /// ray into the mesh from the triangle's centroid. This is infinite if the
+    /// ray leaves the mesh without hitting anything, which only happens for
+    /// meshes that are not closed.
+    pub wall_thickness: Vec<f32>,
+
+    /// Whether each triangle's wall is thinner than the minimum wall thickness.
+    pub is_thin_wall: Vec<bool>,
+
+    /// Whether each triangle belongs to a connected component whose bounding
+    /// box is smaller than the minimum feature size along every axis.
+    pub is_small_feature: Vec<bool>,
+}
+
+impl Mesh {
+    /// Analyzes how well the mesh can be printed on a 3D printer. The mesh
+    /// should be closed and have outward-facing triangles.
+    pub fn analyze_printability(&self, settings: &PrintabilitySettings) -> PrintabilityReport {
+        let triangle_count = self.triangle_vertex_indices.len();
+        let build_direction = settings.build_direction.normalize();
+        let (min, max) = match self.bounding_box() {
+            Some(bounds) => bounds,
+            None => {
+                return PrintabilityReport {
+                    overhang_angles: vec![],
+                    needs_support: vec![],
+                    unsupported_area: 0.0,
+                    wall_thickness: vec![],
+                    is_thin_wall: vec![],
+                    is_small_feature: vec![],
+                }
+            }
+        };
+        let diagonal = (max - min).magnitude();
+        let tolerance = diagonal * 1e-5;
+
+        // Triangles that lie on the lowest layer rest on the build plate.
+        let plate_height = self
+            .positions
+            .iter()
+            .map(|p| p.to_vec().dot(build_direction))
+            .fold(f32::INFINITY, f32::min);
+
+        let mut overhang_angles = Vec::with_capacity(triangle_count);
+        let mut needs_support = Vec::with_capacity(triangle_count);
+        let mut unsupported_area = 0.0;
+        for t in self.triangles_iter() {
+            let normal = t.face_normal();
+            let angle = (-normal.dot(build_direction)).clamp(0.0, 1.0).asin();
+            let (p1, p2, p3) = t.positions();
+            let is_on_plate = [p1, p2, p3]
+                .iter()
+                .all(|p| p.to_vec().dot(build_direction) - plate_height <= tolerance);
+            let is_unsupported = angle > settings.max_overhang_angle.0 && !is_on_plate;
+            if is_unsupported {
+                unsupported_area += t.area();
+            }
+            overhang_angles.push(angle);
+            needs_support.push(is_unsupported);
+        }
+
+        let bvh = self.bvh();
+        let wall_thickness: Vec<f32> = self
+            .triangles_iter()
+            .map(|t| {
+                let normal = t.face_normal();
+                if normal.magnitude2() == 0.0 {
+                    return f32::INFINITY;
+                }
+                // Start just inside the surface so that the ray doesn't hit the
+                // triangle that it starts from.
+                let origin = t.centroid() - normal * tolerance;
+                bvh.cast_ray(origin, -normal, 2.0 * diagonal)
+                    .map_or(f32::INFINITY, |hit| hit.distance + tolerance)
+            })
+            .collect();
+        let is_thin_wall = wall_thickness
+            .iter()
+            .map(|&thickness| thickness < settings.min_wall_thickness)
+            .collect();
+
+        let mut is_small_feature = vec![false; triangle_count];
+        for component in self.component_triangle_indices() {
+            let bounds = component.iter().fold(Aabb::empty(), |bounds, &t| {
+                let (p1, p2, p3) = self.triangle_at(t).positions();
+                bounds.including(p1).including(p2).including(p3)
+            });
+            let size = bounds.max - bounds.min;
+            if size.x.max(size.y).max(size.z) < settings.min_feature_size {
+                for &t in &component {
+                    is_small_feature[t] = true;
+                }
+            }
+        }
+
+        PrintabilityReport {
+            overhang_angles,
+            needs_support,
+            unsupported_area,
+            wall_thickness,
+            is_thin_wall,
+            is_small_feature,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn cuboid_at(size: Vector3<f32>, center: Vector3<f32>) -> Mesh {
+        let mut mesh = MeshBuilder::cuboid(size, 1).build();
+        mesh.translate(center);
+        mesh
+    }
+
+    #[test]
+    fn cuboid_on_plate_needs_no_support() {
+        let mesh = cuboid_at(Vector3::new(10.0, 10.0, 10.0), Vector3::new(0.0, 0.0, 5.0));
+        let report = mesh.analyze_printability(&PrintabilitySettings::default());
+
+        for (t, triangle) in mesh.triangles_iter().enumerate() {
+            let expected = if triangle.face_normal().z < -0.5 {
+                FRAC_PI_2
+            } else {
+                0.0
+            };
+            assert!((report.overhang_angles[t] - expected).abs() < 1e-5);
+            assert!((report.wall_thickness[t] - 10.0).abs() < 1e-3);
+        }
+        assert!(report.needs_support.iter().all(|&b| !b));
+        assert_eq!(report.unsupported_area, 0.0);
+        assert!(report.is_thin_wall.iter().all(|&b| !b));
+        assert!(report.is_small_feature.iter().all(|&b| !b));
+    }
+
+    #[test]
+    fn raised_overhang_needs_support() {
+        // A 10×10 slab on a 2×2 column.
+        let mut mesh = cuboid_at(Vector3::new(2.0, 2.0, 5.0), Vector3::new(0.0, 0.0, 2.5));
+        mesh.append(&cuboid_at(
+            Vector3::new(10.0, 10.0, 2.0),
+            Vector3::new(0.0, 0.0, 6.0),
+        ));
+        let report = mesh.analyze_printability(&PrintabilitySettings::default());
+
+        for (t, triangle) in mesh.triangles_iter().enumerate() {
+            let (p1, p2, p3) = triangle.positions();
+            let is_slab_bottom = triangle.face_normal().z < -0.5 && p1.z == 5.0;
+            assert_eq!(
+                report.needs_support[t],
+                is_slab_bottom,
+                "{:?}",
+                (p1, p2, p3)
+            );
+        }
+        assert!((report.unsupported_area - 100.0).abs() < 1e-3);
+
+        // Tipping the part onto its side changes which faces overhang.
+        let settings = PrintabilitySettings {
+            build_direction: Vector3::new(1.0, 0.0, 0.0),
+            ..PrintabilitySettings::default()
+        };
+        let report = mesh.analyze_printability(&settings);
+        // The column's -X side is not on the plate, but the slab's is.
+        assert!((report.unsupported_area - 2.0 * 5.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn finds_thin_walls_and_small_features() {
+        let mut mesh = cuboid_at(Vector3::new(10.0, 10.0, 0.5), Vector3::new(0.0, 0.0, 0.25));
+        let large_triangle_count = mesh.triangle_vertex_indices.len();
+        mesh.append(&cuboid_at(
+            Vector3::new(1.0, 1.0, 1.0),
+            Vector3::new(20.0, 0.0, 0.5),
+        ));
+        let report = mesh.analyze_printability(&PrintabilitySettings::default());
+
+        for (t, triangle) in mesh.triangles_iter().enumerate() {
+            let is_large = t < large_triangle_count;
+            let is_horizontal = triangle.face_normal().z.abs() > 0.5;
+            assert_eq!(report.is_thin_wall[t], is_large && is_horizontal);
+            assert_eq!(report.is_small_feature[t], !is_large);
+        }
+    }
+
+    #[test]
+    fn empty_mesh_has_empty_report() {
+        let report = Mesh::default().analyze_printability(&PrintabilitySettings::default());
+        assert!(report.overhang_angles.is_empty());
+        assert_eq!(report.unsupported_area, 0.0);
+    }
+}
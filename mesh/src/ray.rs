@@ -0,0 +1,223 @@
+use super::bvh::{Bvh, BvhNodeKind};
+use super::Mesh;
+use cgmath::{InnerSpace, Point3, Vector3};
+
+/// The first point at which a ray hits a mesh's surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// The index of the triangle that was hit.
+    pub triangle: usize,
+
+    /// The point that was hit.
+    pub point: Point3<f32>,
+
+    /// The barycentric coordinates of the hit point with respect to the
+    /// triangle's first, second, and third vertices.
+    pub barycentric: Vector3<f32>,
+
+    /// The distance along the ray from its origin to the hit point.
+    pub distance: f32,
+}
+
+impl<'msh> Bvh<'msh> {
+    /// Returns the first point at which the ray from `origin` in `direction`
+    /// hits the mesh, or `None` if it hits nothing within `max_distance`.
+    /// Triangles are hit from either side. The direction does not need to be
+    /// normalized.
+    pub fn cast_ray(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RayHit> {
+        let direction = direction.normalize();
+        if self.nodes.is_empty() || !direction.x.is_finite() {
+            return None;
+        }
+
+        let inverse_direction =
+            Vector3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        let mut best: Option<RayHit> = None;
+        let mut best_distance = max_distance;
+        let mut stack = vec![0];
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            match node.bounds.ray_entry(origin, inverse_direction) {
+                Some(entry) if entry <= best_distance => {}
+                _ => continue,
+            }
+
+            match node.kind {
+                BvhNodeKind::Leaf { start, count } => {
+                    for &t in &self.triangle_indices[start..start + count] {
+                        let (a, b, c) = self.mesh.triangle_at(t).positions();
+                        if let Some((distance, barycentric)) =
+                            intersect_triangle(origin, direction, a, b, c)
+                        {
+                            if distance <= best_distance {
+                                best_distance = distance;
+                                best = Some(RayHit {
+                                    triangle: t,
+                                    point: origin + direction * distance,
+                                    barycentric,
+                                    distance,
+                                });
+                            }
+                        }
+                    }
+                }
+                BvhNodeKind::Interior { right } => {
+                    stack.push(right);
+                    stack.push(node_index + 1);
+                }
+            }
+        }
+
+        best
+    }
+}
+
+impl Mesh {
+    /// Returns the first point at which the ray from `origin` in `direction`
+    /// hits the mesh, or `None` if it hits nothing within `max_distance`.
+    ///
+    /// This builds a bounding volume hierarchy for every call. Use
+    /// [`Mesh::bvh`] to cast many rays against the same mesh.
+    pub fn cast_ray(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RayHit> {
+        self.bvh().cast_ray(origin, direction, max_distance)
+    }
+}
+
+/// Returns the distance along the ray at which it intersects the triangle and
+/// the barycentric coordinates of the intersection, or `None` if it misses.
+/// `direction` must be normalized.
+///
+/// This is the Möller-Trumbore algorithm.
+fn intersect_triangle(
+    origin: Point3<f32>,
+    direction: Vector3<f32>,
+    a: Point3<f32>,
+    b: Point3<f32>,
+    c: Point3<f32>,
+) -> Option<(f32, Vector3<f32>)> {
+    let ab = b - a;
+    let ac = c - a;
+    let p = direction.cross(ac);
+    let determinant = ab.dot(p);
+    if determinant.abs() < f32::EPSILON * ab.magnitude() * ac.magnitude() {
+        return None;
+    }
+
+    let inverse_determinant = 1.0 / determinant;
+    let s = origin - a;
+    let u = s.dot(p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(ab);
+    let v = direction.dot(q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let distance = ac.dot(q) * inverse_determinant;
+    if distance < 0.0 {
+        return None;
+    }
+
+    Some((distance, Vector3::new(1.0 - u - v, u, v)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+    use cgmath::EuclideanSpace;
+
+    fn right_triangle() -> [Point3<f32>; 3] {
+        [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ]
+    }
+
+    fn intersect(origin: Point3<f32>, direction: Vector3<f32>) -> Option<(f32, Vector3<f32>)> {
+        let [a, b, c] = right_triangle();
+        intersect_triangle(origin, direction.normalize(), a, b, c)
+    }
+
+    #[test]
+    fn intersects_triangle_from_either_side() {
+        let down = Vector3::new(0.0, 0.0, -1.0);
+        let (distance, barycentric) = intersect(Point3::new(0.25, 0.5, 2.0), down).unwrap();
+        assert_eq!(distance, 2.0);
+        assert_eq!(barycentric, Vector3::new(0.25, 0.25, 0.5));
+
+        let (distance, _) = intersect(Point3::new(0.25, 0.25, -3.0), -down).unwrap();
+        assert_eq!(distance, 3.0);
+    }
+
+    #[test]
+    fn intersects_edges_and_corners() {
+        let down = Vector3::new(0.0, 0.0, -1.0);
+        let (_, barycentric) = intersect(Point3::new(0.5, 0.5, 1.0), down).unwrap();
+        assert_eq!(barycentric, Vector3::new(0.0, 0.5, 0.5));
+        let (_, barycentric) = intersect(Point3::new(0.0, 0.0, 1.0), down).unwrap();
+        assert_eq!(barycentric, Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn misses_triangle() {
+        let down = Vector3::new(0.0, 0.0, -1.0);
+        // Outside of the triangle.
+        assert_eq!(intersect(Point3::new(0.6, 0.6, 1.0), down), None);
+        assert_eq!(intersect(Point3::new(-0.1, 0.5, 1.0), down), None);
+        // Pointing away from it.
+        assert_eq!(intersect(Point3::new(0.25, 0.25, 1.0), -down), None);
+        // Parallel to its plane.
+        assert_eq!(
+            intersect(Point3::new(-1.0, 0.25, 0.0), Vector3::new(1.0, 0.0, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn cast_ray_finds_nearest_triangle() {
+        let mesh = MeshBuilder::cuboid(Vector3::new(1.0, 2.0, 3.0), 2).build();
+        let origin = Point3::new(0.1, 0.2, 5.0);
+        let direction = Vector3::new(0.0, 0.0, -2.0);
+
+        let hit = mesh.cast_ray(origin, direction, f32::INFINITY).unwrap();
+        assert_eq!(hit.distance, 3.5);
+        assert_eq!(hit.point, Point3::new(0.1, 0.2, 1.5));
+        let triangle = mesh.triangle_at(hit.triangle);
+        let (a, b, c) = triangle.positions();
+        assert!(a.z == 1.5 && b.z == 1.5 && c.z == 1.5);
+        let interpolated = a.to_vec() * hit.barycentric.x
+            + b.to_vec() * hit.barycentric.y
+            + c.to_vec() * hit.barycentric.z;
+        assert!((interpolated - Vector3::new(0.1, 0.2, 1.5)).magnitude() < 1e-5);
+
+        assert_eq!(mesh.cast_ray(origin, direction, 3.0), None);
+        assert_eq!(
+            mesh.cast_ray(origin, Vector3::new(0.0, 0.0, 1.0), f32::INFINITY),
+            None
+        );
+        assert_eq!(
+            mesh.cast_ray(origin, Vector3::new(0.0, 0.0, 0.0), f32::INFINITY),
+            None
+        );
+        assert_eq!(
+            Mesh::default().cast_ray(origin, direction, f32::INFINITY),
+            None
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use super::{Error, Mesh, Result};
+use cgmath::Vector4;
+
+impl Mesh {
+    /// Generates a tangent for each vertex in the mesh using the MikkTSpace
+    /// algorithm, which is what most tools use when baking normal maps. The
+    /// mesh must have UV coordinates.
+    ///
+    /// MikkTSpace computes a tangent for every corner of every triangle. If a
+    /// vertex is shared by triangles whose tangents differ, such as across a
+    /// mirrored UV seam, then the vertex is split into one copy for each
+    /// distinct tangent. The copies are added after the existing vertices and
+    /// keep all of the original vertex's other attributes.
+    pub fn generate_tangents(&mut self) -> Result<()> {
+        let uvs = self.uvs.as_ref().ok_or(Error::MissingUvs)?;
+
+        let mut geometry = TangentGeometry {
+            mesh: self,
+            uvs,
+            corner_tangents: vec![
+                [Vector4::new(1.0, 0.0, 0.0, 1.0); 3];
+                self.triangle_vertex_indices.len()
+            ],
+        };
+        if !mikktspace::generate_tangents(&mut geometry) {
+            return Err(Error::TangentGeneration);
+        }
+        let corner_tangents = geometry.corner_tangents;
+
+        // The tangents that each vertex has been given so far, with the index
+        // of the vertex or copy that holds each of them.
+        let mut vertex_tangents: Vec<Vec<(Vector4<f32>, usize)>> =
+            vec![vec![]; self.positions.len()];
+        let mut tangents = vec![Vector4::new(1.0, 0.0, 0.0, 1.0); self.positions.len()];
+        let mut copied_vertices = vec![];
+
+        for (triangle, corners) in self
+            .triangle_vertex_indices
+            .iter_mut()
+            .zip(&corner_tangents)
+        {
+            let (i1, i2, i3) = *triangle;
+            let mut indices = [i1, i2, i3];
+            for (i, &tangent) in indices.iter_mut().zip(corners) {
+                let existing = vertex_tangents[*i].iter().find(|(t, _)| *t == tangent);
+                *i = match existing {
+                    Some(&(_, index)) => index,
+                    None if vertex_tangents[*i].is_empty() => {
+                        tangents[*i] = tangent;
+                        vertex_tangents[*i].push((tangent, *i));
+                        *i
+                    }
+                    None => {
+                        let index = tangents.len();
+                        tangents.push(tangent);
+                        copied_vertices.push(*i);
+                        vertex_tangents[*i].push((tangent, index));
+                        index
+                    }
+                };
+            }
+            *triangle = (indices[0], indices[1], indices[2]);
+        }
+
+        self.append_vertex_copies(&copied_vertices);
+        self.tangents = Some(tangents);
+        Ok(())
+    }
+
+    /// Appends a copy of each of the given vertices, with all of its
+    /// attributes, to the end of the mesh's vertices.
+    fn append_vertex_copies(&mut self, indices: &[usize]) {
+        if indices.is_empty() {
+            return;
+        }
+
+        fn copy<T: Copy>(values: &mut Vec<T>, indices: &[usize]) {
+            for &i in indices {
+                values.push(values[i]);
+            }
+        }
+
+        copy(&mut self.positions, indices);
+        copy(&mut self.normals, indices);
+        if let Some(uvs) = &mut self.uvs {
+            copy(uvs, indices);
+        }
+        if let Some(colors) = &mut self.colors {
+            copy(colors, indices);
+        }
+        for data in self.attributes.values_mut() {
+            let copies = data.select(indices);
+            let len = copies.len();
+            data.extend_from(Some(&copies), len);
+        }
+    }
+}
+
+/// Adapts a mesh to the interface that the `mikktspace` crate expects.
+struct TangentGeometry<'msh> {
+    mesh: &'msh Mesh,
+    uvs: &'msh [cgmath::Point2<f32>],
+    corner_tangents: Vec<[Vector4<f32>; 3]>,
+}
+
+impl<'msh> TangentGeometry<'msh> {
+    fn vertex_index(&self, face: usize, vert: usize) -> usize {
+        let (i1, i2, i3) = self.mesh.triangle_vertex_indices[face];
+        match vert {
+            0 => i1,
+            1 => i2,
+            _ => i3,
+        }
+    }
+}
+
+impl<'msh> mikktspace::Geometry for TangentGeometry<'msh> {
+    fn num_faces(&self) -> usize {
+        self.mesh.triangle_vertex_indices.len()
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.mesh.positions[self.vertex_index(face, vert)].into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.mesh.normals[self.vertex_index(face, vert)].into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.uvs[self.vertex_index(face, vert)].into()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.corner_tangents[face][vert] = tangent.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttributeData, MeshBuilder};
+    use cgmath::{InnerSpace, Point2, Point3, Vector3};
+
+    fn assert_tangent_eq(actual: Vector4<f32>, expected: Vector4<f32>) {
+        assert!(
+            (actual - expected).magnitude() < 1e-5,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn plane_tangents_follow_u() {
+        let mut mesh = MeshBuilder::plane(2.0, 1.0, 3, 2).build();
+        let vertex_count = mesh.positions.len();
+        mesh.generate_tangents().unwrap();
+
+        let tangents = mesh.tangents.as_ref().unwrap();
+        assert_eq!(tangents.len(), vertex_count);
+        assert_eq!(mesh.positions.len(), vertex_count);
+        let w = tangents[0].w;
+        assert!(w == 1.0 || w == -1.0);
+        for &tangent in tangents {
+            assert_tangent_eq(tangent, Vector4::new(1.0, 0.0, 0.0, w));
+        }
+    }
+
+    #[test]
+    fn splits_vertices_on_mirrored_uv_seam() {
+        // A square whose second triangle has its texture mirrored along the
+        // shared diagonal, so the corners on the diagonal need two tangents.
+        let mut mesh = MeshBuilder::new(
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(1.0, 1.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            vec![Vector3::new(0.0, 0.0, 1.0); 4],
+            vec![(0, 1, 2), (0, 2, 3)],
+        )
+        .uvs(vec![
+            Point2::new(0.0, 0.0),
+            Point2::new(1.0, 0.0),
+            Point2::new(1.0, 1.0),
+            Point2::new(2.0, 1.0),
+        ])
+        .attribute("id", AttributeData::Int(vec![10, 11, 12, 13]))
+        .build();
+        mesh.generate_tangents().unwrap();
+
+        assert_eq!(mesh.triangle_vertex_indices, vec![(0, 1, 2), (4, 5, 3)]);
+        assert_eq!(mesh.positions[4], mesh.positions[0]);
+        assert_eq!(mesh.positions[5], mesh.positions[2]);
+        assert_eq!(mesh.normals.len(), 6);
+        assert_eq!(mesh.uvs.as_ref().unwrap()[5], Point2::new(1.0, 1.0));
+        assert_eq!(
+            mesh.attributes["id"],
+            AttributeData::Int(vec![10, 11, 12, 13, 10, 12])
+        );
+
+        let tangents = mesh.tangents.as_ref().unwrap();
+        for &i in &[0, 1, 2] {
+            assert_tangent_eq(tangents[i], Vector4::new(1.0, 0.0, 0.0, 1.0));
+        }
+        for &i in &[3, 4, 5] {
+            assert_tangent_eq(tangents[i], Vector4::new(-1.0, 0.0, 0.0, -1.0));
+        }
+    }
+
+    #[test]
+    fn shared_sphere_vertices_keep_one_tangent() {
+        let mut mesh = MeshBuilder::sphere(1.0, 16, 8).build();
+        let vertex_count = mesh.positions.len();
+        mesh.generate_tangents().unwrap();
+        assert_eq!(mesh.positions.len(), vertex_count);
+
+        let tangents = mesh.tangents.as_ref().unwrap();
+        for (tangent, normal) in tangents.iter().zip(&mesh.normals) {
+            assert!((tangent.truncate().magnitude() - 1.0).abs() < 1e-4);
+            assert!(tangent.truncate().dot(*normal).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn requires_uvs() {
+        let mut mesh = MeshBuilder::plane(1.0, 1.0, 1, 1).build();
+        mesh.uvs = None;
+        assert!(matches!(mesh.generate_tangents(), Err(Error::MissingUvs)));
+        assert!(mesh.tangents.is_none());
+    }
+}
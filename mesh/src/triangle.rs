@@ -1,5 +1,6 @@
 use super::Mesh;
-use cgmath::{Point2, Point3};
+use cgmath::{EuclideanSpace, InnerSpace, Point2, Point3, Vector3};
+use std::ops::{Add, Mul};
 
 /// A reference to an individual triangle in a mesh.
 #[derive(Debug, Clone, Copy)]
@@ -9,21 +10,40 @@ pub struct Triangle<'msh> {
 }
 
 impl<'msh> Triangle<'msh> {
+    /// Returns the index of the triangle in the mesh.
+    pub fn index(&self) -> usize {
+        self.index_in_mesh
+    }
+
+    /// Returns the indices of the triangle's vertices in the mesh.
+    pub fn vertex_indices(&self) -> (usize, usize, usize) {
+        self.mesh.triangle_vertex_indices[self.index_in_mesh]
+    }
+
     /// Returns the positions of the triangle's vertices in world space.
     pub fn positions(&self) -> (Point3<f32>, Point3<f32>, Point3<f32>) {
-        let (i1, i2, i3) = self.mesh.triangle_vertex_indices[self.index_in_mesh];
+        let (i1, i2, i3) = self.vertex_indices();
         let p1 = self.mesh.positions[i1];
         let p2 = self.mesh.positions[i2];
         let p3 = self.mesh.positions[i3];
         (p1, p2, p3)
     }
 
+    /// Returns the normals of the triangle's vertices.
+    pub fn normals(&self) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>) {
+        let (i1, i2, i3) = self.vertex_indices();
+        let n1 = self.mesh.normals[i1];
+        let n2 = self.mesh.normals[i2];
+        let n3 = self.mesh.normals[i3];
+        (n1, n2, n3)
+    }
+
     /// Returns the UV coordinates for each of the triangle's vertices. If the
     /// mesh does not contain UV coordinates, then default coordinates are
     /// returned.
     pub fn uvs(&self) -> (Point2<f32>, Point2<f32>, Point2<f32>) {
         if let Some(uvs) = &self.mesh.uvs {
-            let (i1, i2, i3) = self.mesh.triangle_vertex_indices[self.index_in_mesh];
+            let (i1, i2, i3) = self.vertex_indices();
             (uvs[i1], uvs[i2], uvs[i3])
         } else {
             (
@@ -33,6 +53,77 @@ impl<'msh> Triangle<'msh> {
             )
         }
     }
+
+    /// Returns the unit normal of the plane that contains the triangle. The
+    /// normal faces the side from which the vertices appear in
+    /// counterclockwise order. Returns a zero vector if the triangle is
+    /// degenerate.
+    pub fn face_normal(&self) -> Vector3<f32> {
+        let cross = self.cross();
+        if cross.magnitude2() > 0.0 {
+            cross.normalize()
+        } else {
+            Vector3::new(0.0, 0.0, 0.0)
+        }
+    }
+
+    /// Returns the area of the triangle.
+    pub fn area(&self) -> f32 {
+        self.cross().magnitude() / 2.0
+    }
+
+    /// Returns the average of the positions of the triangle's vertices.
+    pub fn centroid(&self) -> Point3<f32> {
+        let (p1, p2, p3) = self.positions();
+        Point3::new(
+            (p1.x + p2.x + p3.x) / 3.0,
+            (p1.y + p2.y + p3.y) / 3.0,
+            (p1.z + p2.z + p3.z) / 3.0,
+        )
+    }
+
+    /// Interpolates a per-vertex attribute across the triangle. `values`
+    /// contains a value for each vertex in the mesh, and `barycentric`
+    /// contains the weights of the triangle's first, second, and third
+    /// vertices.
+    pub fn interpolate<T>(&self, values: &[T], barycentric: Vector3<f32>) -> T
+    where
+        T: Copy + Add<Output = T> + Mul<f32, Output = T>,
+    {
+        let (i1, i2, i3) = self.vertex_indices();
+        values[i1] * barycentric.x + values[i2] * barycentric.y + values[i3] * barycentric.z
+    }
+
+    /// Returns the position at the given barycentric coordinates.
+    pub fn position_at(&self, barycentric: Vector3<f32>) -> Point3<f32> {
+        let (p1, p2, p3) = self.positions();
+        Point3::from_vec(
+            p1.to_vec() * barycentric.x + p2.to_vec() * barycentric.y + p3.to_vec() * barycentric.z,
+        )
+    }
+
+    /// Returns the interpolated unit normal at the given barycentric
+    /// coordinates.
+    pub fn normal_at(&self, barycentric: Vector3<f32>) -> Vector3<f32> {
+        self.interpolate(&self.mesh.normals, barycentric)
+            .normalize()
+    }
+
+    /// Returns the interpolated UV coordinates at the given barycentric
+    /// coordinates. See [`Triangle::uvs`] for meshes without UV coordinates.
+    pub fn uv_at(&self, barycentric: Vector3<f32>) -> Point2<f32> {
+        let (uv1, uv2, uv3) = self.uvs();
+        Point2::new(
+            uv1.x * barycentric.x + uv2.x * barycentric.y + uv3.x * barycentric.z,
+            uv1.y * barycentric.x + uv2.y * barycentric.y + uv3.y * barycentric.z,
+        )
+    }
+
+    /// Returns the cross product of the triangle's first two edges.
+    fn cross(&self) -> Vector3<f32> {
+        let (p1, p2, p3) = self.positions();
+        (p2 - p1).cross(p3 - p1)
+    }
 }
 
 impl<'msh> Mesh {
@@ -44,7 +135,108 @@ impl<'msh> Mesh {
     }
 
     pub fn triangles(&'msh self) -> Vec<Triangle<'msh>> {
-        let triangle_count = self.triangle_vertex_indices.len();
-        (0..triangle_count).map(|i| self.triangle_at(i)).collect()
+        self.triangles_iter().collect()
+    }
+
+    /// Returns an iterator over the triangles in the mesh that does not
+    /// allocate.
+    pub fn triangles_iter(&'msh self) -> impl ExactSizeIterator<Item = Triangle<'msh>> {
+        (0..self.triangle_vertex_indices.len()).map(move |i| self.triangle_at(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+
+    /// Returns a mesh with a right triangle in the XY plane whose legs have
+    /// length 1, wound counterclockwise when seen from +Z.
+    fn right_triangle() -> Mesh {
+        MeshBuilder::new(
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            vec![
+                Vector3::new(0.0, 0.0, 1.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ],
+            vec![(0, 1, 2)],
+        )
+        .uvs(vec![
+            Point2::new(0.0, 0.0),
+            Point2::new(2.0, 0.0),
+            Point2::new(0.0, 4.0),
+        ])
+        .build()
+    }
+
+    #[test]
+    fn measures_right_triangle() {
+        let mesh = right_triangle();
+        let triangle = mesh.triangle_at(0);
+        assert_eq!(triangle.index(), 0);
+        assert_eq!(triangle.face_normal(), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(triangle.area(), 0.5);
+        assert_eq!(triangle.centroid(), Point3::new(1.0 / 3.0, 1.0 / 3.0, 0.0));
+    }
+
+    #[test]
+    fn reversed_winding_flips_face_normal() {
+        let mut mesh = right_triangle();
+        mesh.triangle_vertex_indices = vec![(0, 2, 1)];
+        let triangle = mesh.triangle_at(0);
+        assert_eq!(triangle.face_normal(), Vector3::new(0.0, 0.0, -1.0));
+        assert_eq!(triangle.area(), 0.5);
+    }
+
+    #[test]
+    fn degenerate_triangle_has_zero_normal_and_area() {
+        let mut mesh = right_triangle();
+        mesh.positions[2] = Point3::new(2.0, 0.0, 0.0);
+        let triangle = mesh.triangle_at(0);
+        assert_eq!(triangle.face_normal(), Vector3::new(0.0, 0.0, 0.0));
+        assert_eq!(triangle.area(), 0.0);
+    }
+
+    #[test]
+    fn interpolates_at_barycentric_coordinates() {
+        let mesh = right_triangle();
+        let triangle = mesh.triangle_at(0);
+
+        let corner = Vector3::new(1.0, 0.0, 0.0);
+        assert_eq!(triangle.position_at(corner), mesh.positions[0]);
+        assert_eq!(triangle.normal_at(corner), mesh.normals[0]);
+        assert_eq!(triangle.uv_at(corner), Point2::new(0.0, 0.0));
+
+        let second = Vector3::new(0.0, 1.0, 0.0);
+        assert_eq!(triangle.position_at(second), mesh.positions[1]);
+        assert_eq!(triangle.uv_at(second), Point2::new(2.0, 0.0));
+
+        let middle = Vector3::new(0.0, 0.5, 0.5);
+        assert_eq!(triangle.position_at(middle), Point3::new(0.5, 0.5, 0.0));
+        let normal = triangle.normal_at(middle);
+        assert!((normal - Vector3::new(1.0, 1.0, 0.0).normalize()).magnitude() < 1e-6);
+        assert_eq!(triangle.uv_at(middle), Point2::new(1.0, 2.0));
+
+        let values = [1.0, 4.0, 7.0];
+        assert_eq!(
+            triangle.interpolate(&values, Vector3::new(0.5, 0.25, 0.25)),
+            3.25
+        );
+    }
+
+    #[test]
+    fn missing_uvs_use_default_coordinates() {
+        let mut mesh = right_triangle();
+        mesh.uvs = None;
+        let triangle = mesh.triangle_at(0);
+        assert_eq!(
+            triangle.uv_at(Vector3::new(0.0, 0.0, 1.0)),
+            Point2::new(1.0, 1.0)
+        );
     }
 }
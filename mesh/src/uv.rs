@@ -0,0 +1,242 @@
+use super::Mesh;
+use cgmath::{Point2, Point3, Vector3};
+use std::f32::consts::PI;
+
+/// One of the three coordinate axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    /// Returns the component of the vector along this axis followed by the
+    /// components along the two other axes, in cyclic order. For example, the
+    /// Y axis returns `(y, z, x)`.
+    fn split(self, v: Vector3<f32>) -> (f32, f32, f32) {
+        match self {
+            Axis::X => (v.x, v.y, v.z),
+            Axis::Y => (v.y, v.z, v.x),
+            Axis::Z => (v.z, v.x, v.y),
+        }
+    }
+
+    /// Returns the axis along which the vector has the largest magnitude.
+    fn dominant(v: Vector3<f32>) -> Self {
+        let (x, y, z) = (v.x.abs(), v.y.abs(), v.z.abs());
+        if x >= y && x >= z {
+            Axis::X
+        } else if y >= z {
+            Axis::Y
+        } else {
+            Axis::Z
+        }
+    }
+}
+
+impl Mesh {
+    /// Replaces the mesh's UV coordinates with a planar projection onto the
+    /// plane perpendicular to `axis`. The coordinates are measured in world
+    /// units multiplied by `scale`, so a texture repeats every `1 / scale`
+    /// units.
+    pub fn generate_planar_uvs(&mut self, axis: Axis, scale: f32) {
+        let uvs = self
+            .positions
+            .iter()
+            .map(|p| planar_uv(*p, axis, scale))
+            .collect();
+        self.uvs = Some(uvs);
+    }
+
+    /// Replaces the mesh's UV coordinates with a box projection. Each vertex is
+    /// projected onto the plane that its normal faces most directly. The
+    /// coordinates are scaled the same way as in
+    /// [`Mesh::generate_planar_uvs`].
+    pub fn generate_box_uvs(&mut self, scale: f32) {
+        let uvs = self
+            .positions
+            .iter()
+            .zip(self.normals.iter())
+            .map(|(p, n)| planar_uv(*p, Axis::dominant(*n), scale))
+            .collect();
+        self.uvs = Some(uvs);
+    }
+
+    /// Replaces the mesh's UV coordinates with a cylindrical projection around
+    /// a line parallel to `axis` through the center of the mesh's bounding box.
+    /// Before scaling, U is the angle around the axis, mapped to `[0, 1]`, and
+    /// V is the height along the axis, mapped to `[0, 1]` over the bounding
+    /// box.
+    pub fn generate_cylindrical_uvs(&mut self, axis: Axis, scale: f32) {
+        let (min, max) = match self.bounding_box() {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let center = min + (max - min) / 2.0;
+        let (height, _, _) = axis.split(max - min);
+        let height = if height > 0.0 { height } else { 1.0 };
+
+        let uvs = self
+            .positions
+            .iter()
+            .map(|p| {
+                let (h, a, b) = axis.split(p - center);
+                let u = angle_fraction(a, b);
+                let v = h / height + 0.5;
+                Point2::new(u * scale, v * scale)
+            })
+            .collect();
+        self.uvs = Some(uvs);
+    }
+
+    /// Replaces the mesh's UV coordinates with a spherical projection around
+    /// the center of the mesh's bounding box, with the poles on `axis`. Before
+    /// scaling, U is the azimuthal angle around the axis, mapped to `[0, 1]`,
+    /// and V is the polar angle from the axis, mapped to `[0, 1]`.
+    pub fn generate_spherical_uvs(&mut self, axis: Axis, scale: f32) {
+        let (min, max) = match self.bounding_box() {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let center = min + (max - min) / 2.0;
+
+        let uvs = self
+            .positions
+            .iter()
+            .map(|p| {
+                let (h, a, b) = axis.split(p - center);
+                let u = angle_fraction(a, b);
+                let v = (a * a + b * b).sqrt().atan2(h) / PI;
+                Point2::new(u * scale, v * scale)
+            })
+            .collect();
+        self.uvs = Some(uvs);
+    }
+}
+
+/// Projects the point onto the plane perpendicular to `axis`.
+fn planar_uv(p: Point3<f32>, axis: Axis, scale: f32) -> Point2<f32> {
+    let (_, u, v) = axis.split(Vector3::new(p.x, p.y, p.z));
+    Point2::new(u * scale, v * scale)
+}
+
+/// Returns the angle of the vector `(a, b)` counterclockwise from the `a` axis,
+/// mapped from `[0, 2π)` to `[0, 1)`.
+fn angle_fraction(a: f32, b: f32) -> f32 {
+    let angle = b.atan2(a);
+    let angle = if angle < 0.0 { angle + 2.0 * PI } else { angle };
+    angle / (2.0 * PI)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+    use cgmath::InnerSpace;
+
+    fn assert_uv_eq(actual: Point2<f32>, expected: Point2<f32>) {
+        assert!(
+            (actual.x - expected.x).abs() < 1e-5 && (actual.y - expected.y).abs() < 1e-5,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn planar_uvs_project_along_the_axis() {
+        let mut mesh = MeshBuilder::plane(1.0, 1.0, 1, 1).build();
+        mesh.translate(Vector3::new(0.5, 0.5, 0.0));
+        mesh.generate_planar_uvs(Axis::Z, 1.0);
+        for (p, uv) in mesh.positions.iter().zip(mesh.uvs.as_ref().unwrap()) {
+            assert_uv_eq(*uv, Point2::new(p.x, p.y));
+            assert!(uv.x == 0.0 || uv.x == 1.0);
+            assert!(uv.y == 0.0 || uv.y == 1.0);
+        }
+
+        let p = Point3::new(1.0, 2.0, 3.0);
+        assert_eq!(planar_uv(p, Axis::X, 1.0), Point2::new(2.0, 3.0));
+        assert_eq!(planar_uv(p, Axis::Y, 1.0), Point2::new(3.0, 1.0));
+        assert_eq!(planar_uv(p, Axis::Z, 2.0), Point2::new(2.0, 4.0));
+    }
+
+    #[test]
+    fn box_uvs_use_the_dominant_normal_axis() {
+        let mut mesh = MeshBuilder::cuboid(Vector3::new(1.0, 1.0, 1.0), 1).build();
+        mesh.translate(Vector3::new(0.5, 0.5, 0.5));
+        mesh.generate_box_uvs(1.0);
+
+        let uvs = mesh.uvs.as_ref().unwrap();
+        for ((p, n), uv) in mesh.positions.iter().zip(&mesh.normals).zip(uvs) {
+            let expected = planar_uv(*p, Axis::dominant(*n), 1.0);
+            assert_eq!(*uv, expected);
+            assert!(uv.x == 0.0 || uv.x == 1.0);
+            assert!(uv.y == 0.0 || uv.y == 1.0);
+        }
+        assert_eq!(Axis::dominant(Vector3::new(0.2, -0.9, 0.3)), Axis::Y);
+        assert_eq!(Axis::dominant(Vector3::new(0.1, 0.1, -0.5)), Axis::Z);
+    }
+
+    #[test]
+    fn cylindrical_uvs_wrap_around_the_axis() {
+        let mut mesh = MeshBuilder::cuboid(Vector3::new(2.0, 2.0, 4.0), 1).build();
+        mesh.translate(Vector3::new(5.0, 0.0, 0.0));
+        mesh.generate_cylindrical_uvs(Axis::Z, 2.0);
+
+        let uvs = mesh.uvs.as_ref().unwrap();
+        for (p, uv) in mesh.positions.iter().zip(uvs) {
+            // Measured around the center of the bounding box.
+            let expected_u = match (p.x > 5.0, p.y > 0.0) {
+                (true, true) => 0.125,
+                (false, true) => 0.375,
+                (false, false) => 0.625,
+                (true, false) => 0.875,
+            };
+            let expected_v = if p.z > 0.0 { 1.0 } else { 0.0 };
+            assert_uv_eq(*uv, Point2::new(expected_u * 2.0, expected_v * 2.0));
+        }
+    }
+
+    #[test]
+    fn spherical_uvs_handle_seam_and_poles() {
+        let mut mesh = MeshBuilder::sphere(1.0, 8, 4).build();
+        mesh.generate_spherical_uvs(Axis::Z, 1.0);
+
+        let uvs = mesh.uvs.as_ref().unwrap();
+        for (p, uv) in mesh.positions.iter().zip(uvs) {
+            assert!((0.0..1.0).contains(&uv.x), "u = {} at {:?}", uv.x, p);
+            assert!((0.0..=1.0).contains(&uv.y), "v = {} at {:?}", uv.y, p);
+            if p.z == 1.0 {
+                assert_uv_eq(*uv, Point2::new(0.0, 0.0));
+            } else if p.z == -1.0 {
+                assert_uv_eq(*uv, Point2::new(0.0, 1.0));
+            } else if p.y.abs() < 1e-6 && p.x > 0.0 {
+                // Both copies of a seam vertex start the texture.
+                assert_eq!(uv.x, 0.0);
+            }
+        }
+
+        // The equator follows the azimuth.
+        let equator = |x: f32, y: f32| {
+            let i = mesh
+                .positions
+                .iter()
+                .position(|p| (p - Point3::new(x, y, 0.0)).magnitude2() < 1e-10)
+                .unwrap();
+            uvs[i]
+        };
+        assert_uv_eq(equator(0.0, 1.0), Point2::new(0.25, 0.5));
+        assert_uv_eq(equator(-1.0, 0.0), Point2::new(0.5, 0.5));
+        assert_uv_eq(equator(0.0, -1.0), Point2::new(0.75, 0.5));
+    }
+
+    #[test]
+    fn empty_mesh_gets_no_projected_uvs() {
+        let mut mesh = Mesh::default();
+        mesh.generate_cylindrical_uvs(Axis::Y, 1.0);
+        mesh.generate_spherical_uvs(Axis::Y, 1.0);
+        assert!(mesh.uvs.is_none());
+    }
+}
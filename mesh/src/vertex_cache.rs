@@ -0,0 +1,296 @@
+use super::Mesh;
+
+/// The number of vertices in the cache that [`Mesh::optimize_vertex_cache`]
+/// optimizes for. Optimizing for a larger cache than the GPU's still improves
+/// the order for smaller caches.
+const CACHE_SIZE: usize = 32;
+
+// Scoring constants from Tom Forsyth's "Linear-Speed Vertex Cache
+// Optimisation".
+const CACHE_DECAY_POWER: f32 = 1.5;
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+impl Mesh {
+    /// Returns the average cache miss ratio (ACMR) of the mesh's triangle
+    /// order: the number of vertices that a GPU with a FIFO post-transform
+    /// cache of `cache_size` vertices would have to transform, divided by the
+    /// number of triangles. It ranges from `3` in the worst case to about `0.5`
+    /// for large, well ordered meshes. Returns `0` if the mesh has no
+    /// triangles.
+    ///
+    /// Compare the ACMR before and after [`Mesh::optimize_vertex_cache`] to
+    /// measure its effect. Typical cache sizes are 16 to 32 vertices.
+    pub fn acmr(&self, cache_size: usize) -> f32 {
+        if self.triangle_vertex_indices.is_empty() {
+            return 0.0;
+        }
+
+        // The cache holds each vertex's insertion time, and a vertex is in the
+        // cache if it was inserted within the last `cache_size` insertions.
+        let mut inserted_at: Vec<Option<usize>> = vec![None; self.positions.len()];
+        let mut misses = 0;
+        for &(i1, i2, i3) in &self.triangle_vertex_indices {
+            for i in [i1, i2, i3] {
+                let is_cached = inserted_at[i].is_some_and(|time| misses - time < cache_size);
+                if !is_cached {
+                    inserted_at[i] = Some(misses);
+                    misses += 1;
+                }
+            }
+        }
+
+        misses as f32 / self.triangle_vertex_indices.len() as f32
+    }
+
+    /// Reorders the mesh's triangles so that consecutive triangles share
+    /// vertices, which lets the GPU reuse transformed vertices from its
+    /// post-transform cache instead of transforming them again. This uses Tom
+    /// Forsyth's linear-speed algorithm, which works well for any cache size
+    /// without knowing the exact size. Vertices are not changed, and triangles
+    /// keep their materials.
+    ///
+    /// Run [`Mesh::optimize_vertex_fetch`] afterwards to also reorder the
+    /// vertices for memory locality.
+    pub fn optimize_vertex_cache(&mut self) {
+        let order = self.vertex_cache_triangle_order();
+        self.triangle_vertex_indices = order
+            .iter()
+            .map(|&t| self.triangle_vertex_indices[t])
+            .collect();
+        if let Some(materials) = &mut self.triangle_materials {
+            *materials = order.iter().map(|&t| materials[t]).collect();
+        }
+    }
+
+    /// Reorders the mesh's vertices in the order in which the triangles first
+    /// use them, so that the GPU fetches vertex data from memory mostly in
+    /// sequence. Vertices that are not used by any triangle are dropped.
+    pub fn optimize_vertex_fetch(&mut self) {
+        let triangle_indices: Vec<usize> = (0..self.triangle_vertex_indices.len()).collect();
+        *self = self.with_triangles(&triangle_indices, &self.triangle_vertex_indices);
+    }
+
+    /// Returns the indices of the mesh's triangles in the order chosen by
+    /// Forsyth's algorithm.
+    fn vertex_cache_triangle_order(&self) -> Vec<usize> {
+        let triangle_count = self.triangle_vertex_indices.len();
+        let vertices_of = |t: usize| {
+            let (i1, i2, i3) = self.triangle_vertex_indices[t];
+            [i1, i2, i3]
+        };
+
+        // The triangles that use each vertex and have not been emitted yet.
+        let mut vertex_triangles: Vec<Vec<usize>> = vec![vec![]; self.positions.len()];
+        for t in 0..triangle_count {
+            for i in vertices_of(t) {
+                vertex_triangles[i].push(t);
+            }
+        }
+
+        let mut cache_positions: Vec<Option<usize>> = vec![None; self.positions.len()];
+        let mut vertex_scores: Vec<f32> = (0..self.positions.len())
+            .map(|i| vertex_score(None, vertex_triangles[i].len()))
+            .collect();
+        let mut triangle_scores: Vec<f32> = (0..triangle_count)
+            .map(|t| vertices_of(t).iter().map(|&i| vertex_scores[i]).sum())
+            .collect();
+        let mut is_emitted = vec![false; triangle_count];
+
+        // The cache holds the most recently used vertices first. It briefly
+        // grows past its size while a triangle's vertices are added.
+        let mut cache: Vec<usize> = Vec::with_capacity(CACHE_SIZE + 3);
+        let mut order = Vec::with_capacity(triangle_count);
+        let mut next_unemitted = 0;
+        let mut best_triangle = None;
+
+        while order.len() < triangle_count {
+            let triangle = match best_triangle {
+                Some(t) => t,
+                None => {
+                    // No triangle uses a cached vertex, so start over from the
+                    // next triangle in the original order.
+                    while is_emitted[next_unemitted] {
+                        next_unemitted += 1;
+                    }
+                    next_unemitted
+                }
+            };
+            is_emitted[triangle] = true;
+            order.push(triangle);
+
+            // Move the triangle's vertices to the front of the cache.
+            for i in vertices_of(triangle) {
+                vertex_triangles[i].retain(|&t| t != triangle);
+                cache.retain(|&v| v != i);
+                cache.insert(0, i);
+            }
+            let evicted: Vec<usize> = cache.split_off(cache.len().min(CACHE_SIZE));
+
+            // Update the scores of the vertices whose cache positions changed
+            // and of the triangles that use them.
+            for (position, &i) in cache.iter().enumerate() {
+                cache_positions[i] = Some(position);
+            }
+            for &i in &evicted {
+                cache_positions[i] = None;
+            }
+            for &i in cache.iter().chain(&evicted) {
+                let score = vertex_score(cache_positions[i], vertex_triangles[i].len());
+                let delta = score - vertex_scores[i];
+                vertex_scores[i] = score;
+                for &t in &vertex_triangles[i] {
+                    triangle_scores[t] += delta;
+                }
+            }
+
+            // The next triangle is the best one that uses a cached vertex.
+            best_triangle = None;
+            let mut best_score = f32::NEG_INFINITY;
+            for &i in &cache {
+                for &t in &vertex_triangles[i] {
+                    if triangle_scores[t] > best_score {
+                        best_score = triangle_scores[t];
+                        best_triangle = Some(t);
+                    }
+                }
+            }
+        }
+
+        order
+    }
+}
+
+/// Returns the score of a vertex with the given position in the cache and
+/// number of triangles left to emit. Triangles with high scoring vertices are
+/// emitted first.
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.0;
+    }
+
+    let cache_score = match cache_position {
+        None => 0.0,
+        // The vertices of the last triangle get a fixed score so that the
+        // next triangle doesn't favor any particular edge of it.
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scale = 1.0 / (CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        }
+    };
+
+    // Vertices with few triangles left get a boost so that they are finished
+    // off instead of being left behind.
+    let valence_boost =
+        VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER);
+    cache_score + valence_boost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Material, MeshBuilder};
+    use cgmath::{Point3, Vector4};
+
+    /// Returns a sphere whose triangles are in a scrambled order, with
+    /// alternating materials.
+    fn scrambled_sphere() -> Mesh {
+        let mut mesh = MeshBuilder::sphere(1.0, 24, 12).build();
+        let triangle_count = mesh.triangle_vertex_indices.len();
+        // Stepping by a number coprime with the count visits every triangle.
+        let step = (triangle_count / 2..)
+            .find(|s| gcd(*s, triangle_count) == 1)
+            .unwrap();
+        mesh.triangle_vertex_indices = (0..triangle_count)
+            .map(|t| mesh.triangle_vertex_indices[t * step % triangle_count])
+            .collect();
+        let materials = vec![
+            Material::default(),
+            Material::new("red", Vector4::new(1.0, 0.0, 0.0, 1.0)),
+        ];
+        mesh.materials = materials;
+        mesh.triangle_materials = Some((0..triangle_count).map(|t| t % 2).collect());
+        mesh
+    }
+
+    fn gcd(a: usize, b: usize) -> usize {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+
+    /// Returns the positions and material of each triangle.
+    fn triangles(mesh: &Mesh) -> Vec<([Point3<f32>; 3], Option<usize>)> {
+        mesh.triangle_vertex_indices
+            .iter()
+            .enumerate()
+            .map(|(t, &(i1, i2, i3))| {
+                (
+                    [mesh.positions[i1], mesh.positions[i2], mesh.positions[i3]],
+                    mesh.triangle_material(t),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn optimize_vertex_cache_lowers_acmr() {
+        let mut mesh = scrambled_sphere();
+        let before = mesh.acmr(16);
+        mesh.optimize_vertex_cache();
+        let after = mesh.acmr(16);
+        assert!(
+            after < before * 0.5,
+            "ACMR went from {} to {}",
+            before,
+            after
+        );
+        assert!(after < 1.0, "ACMR is {}", after);
+    }
+
+    #[test]
+    fn optimize_vertex_cache_keeps_triangles_and_materials() {
+        let mut mesh = scrambled_sphere();
+        let mut expected = triangles(&mesh);
+        mesh.optimize_vertex_cache();
+        let mut actual = triangles(&mesh);
+        let key = |t: &([Point3<f32>; 3], Option<usize>)| format!("{:?}", t);
+        expected.sort_by_key(key);
+        actual.sort_by_key(key);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn optimize_vertex_fetch_keeps_triangles_and_materials() {
+        let mut mesh = scrambled_sphere();
+        mesh.optimize_vertex_cache();
+        mesh.positions.push(Point3::new(5.0, 5.0, 5.0));
+        mesh.normals.push(mesh.normals[0]);
+        let expected = triangles(&mesh);
+        mesh.optimize_vertex_fetch();
+        assert_eq!(triangles(&mesh), expected);
+        assert_eq!(mesh.positions.len(), mesh.normals.len());
+        assert!(!mesh.positions.contains(&Point3::new(5.0, 5.0, 5.0)));
+
+        // Vertices are first used in order.
+        let mut next = 0;
+        for &(i1, i2, i3) in &mesh.triangle_vertex_indices {
+            for i in [i1, i2, i3] {
+                assert!(i <= next, "vertex {} is used before vertex {}", i, next);
+                if i == next {
+                    next += 1;
+                }
+            }
+        }
+        assert_eq!(next, mesh.positions.len());
+    }
+
+    #[test]
+    fn acmr_of_empty_mesh_is_zero() {
+        assert_eq!(Mesh::default().acmr(16), 0.0);
+    }
+}
@@ -0,0 +1,418 @@
+use super::{Mesh, Result};
+use cgmath::{InnerSpace, Point3, Vector3};
+use std::collections::VecDeque;
+use std::io::Write;
+
+/// Determines which voxels are occupied when voxelizing a mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoxelizeMode {
+    /// Only voxels that intersect the mesh's surface are occupied.
+    Surface,
+
+    /// Voxels that intersect the mesh's surface or that are enclosed by it are
+    /// occupied.
+    Solid,
+}
+
+/// A dense 3D grid of cubic voxels, each of which is either occupied or empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelGrid {
+    /// The number of voxels along the X, Y, and Z axes.
+    pub dimensions: (usize, usize, usize),
+
+    /// The minimum corner of the grid in world space.
+    pub origin: Point3<f32>,
+
+    /// The side length of each voxel.
+    pub voxel_size: f32,
+
+    /// Whether each voxel is occupied. The voxel at `(x, y, z)` is at index
+    /// `x + y * dimensions.0 + z * dimensions.0 * dimensions.1`.
+    pub occupied: Vec<bool>,
+}
+
+impl Mesh {
+    /// Converts the mesh into a grid of cubic voxels. `resolution` is the number
+    /// of voxels along the longest side of the mesh's bounding box, and the
+    /// other sides get as many voxels as are needed to cover the mesh. Returns
+    /// `None` if the mesh is empty.
+    ///
+    /// In [`VoxelizeMode::Solid`], every voxel that cannot be reached from the
+    /// outside of the grid without crossing a surface voxel is filled. Holes in
+    /// the surface that are smaller than a voxel are therefore sealed, which
+    /// makes this robust for meshes that are not quite closed.
+    pub fn voxelize(&self, resolution: usize, mode: VoxelizeMode) -> Option<VoxelGrid> {
+        let (min, max) = self.bounding_box()?;
+        let extent = max - min;
+        let longest = extent.x.max(extent.y).max(extent.z);
+        let resolution = resolution.max(1);
+        let voxel_size = if longest > 0.0 {
+            longest / resolution as f32
+        } else {
+            1.0
+        };
+
+        let dimension = |length: f32| ((length / voxel_size).ceil() as usize).max(1);
+        let mut grid = VoxelGrid {
+            dimensions: (
+                dimension(extent.x),
+                dimension(extent.y),
+                dimension(extent.z),
+            ),
+            origin: min,
+            voxel_size,
+            occupied: vec![],
+        };
+        grid.occupied = vec![false; grid.len()];
+
+        let half = Vector3::new(voxel_size, voxel_size, voxel_size) / 2.0;
+        for t in self.triangles_iter() {
+            let (a, b, c) = t.positions();
+            let lo = grid.voxel_containing(Point3::new(
+                a.x.min(b.x).min(c.x),
+                a.y.min(b.y).min(c.y),
+                a.z.min(b.z).min(c.z),
+            ));
+            let hi = grid.voxel_containing(Point3::new(
+                a.x.max(b.x).max(c.x),
+                a.y.max(b.y).max(c.y),
+                a.z.max(b.z).max(c.z),
+            ));
+
+            for z in lo.2..=hi.2 {
+                for y in lo.1..=hi.1 {
+                    for x in lo.0..=hi.0 {
+                        let index = grid.index(x, y, z);
+                        if grid.occupied[index] {
+                            continue;
+                        }
+                        let center = grid.voxel_center(x, y, z);
+                        if triangle_overlaps_box(a - center, b - center, c - center, half) {
+                            grid.occupied[index] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        if mode == VoxelizeMode::Solid {
+            grid.fill_interior();
+        }
+
+        Some(grid)
+    }
+}
+
+impl VoxelGrid {
+    /// Returns the total number of voxels in the grid.
+    pub fn len(&self) -> usize {
+        self.dimensions.0 * self.dimensions.1 * self.dimensions.2
+    }
+
+    /// Returns true if the grid has no voxels.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the index into `occupied` of the voxel at `(x, y, z)`.
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        x + y * self.dimensions.0 + z * self.dimensions.0 * self.dimensions.1
+    }
+
+    /// Returns whether the voxel at `(x, y, z)` is occupied.
+    pub fn is_occupied(&self, x: usize, y: usize, z: usize) -> bool {
+        self.occupied[self.index(x, y, z)]
+    }
+
+    /// Returns the center of the voxel at `(x, y, z)` in world space.
+    pub fn voxel_center(&self, x: usize, y: usize, z: usize) -> Point3<f32> {
+        self.origin + Vector3::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5) * self.voxel_size
+    }
+
+    /// Returns the coordinates of the occupied voxels. This is a sparse
+    /// representation of the grid.
+    pub fn occupied_voxels(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        let (nx, ny, _) = self.dimensions;
+        self.occupied
+            .iter()
+            .enumerate()
+            .filter(|(_, &occupied)| occupied)
+            .map(move |(i, _)| (i % nx, (i / nx) % ny, i / (nx * ny)))
+    }
+
+    /// Returns the number of occupied voxels.
+    pub fn occupied_count(&self) -> usize {
+        self.occupied.iter().filter(|&&occupied| occupied).count()
+    }
+
+    /// Writes the grid as one byte per voxel, `1` for occupied and `0` for
+    /// empty, in the same order as `occupied`.
+    pub fn write_raw<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let bytes: Vec<u8> = self.occupied.iter().map(|&o| o as u8).collect();
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Writes the grid in the `.binvox` format. The format requires a cubic
+    /// grid, so the grid is padded with empty voxels along its shorter sides.
+    pub fn write_binvox<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let (nx, ny, nz) = self.dimensions;
+        let size = nx.max(ny).max(nz);
+
+        writeln!(writer, "#binvox 1")?;
+        writeln!(writer, "dim {} {} {}", size, size, size)?;
+        writeln!(
+            writer,
+            "translate {} {} {}",
+            self.origin.x, self.origin.y, self.origin.z
+        )?;
+        writeln!(writer, "scale {}", self.voxel_size * size as f32)?;
+        writeln!(writer, "data")?;
+
+        // Voxels are run-length encoded as pairs of bytes: a value followed by
+        // the number of times that it repeats. In binvox order, Y changes
+        // fastest, then Z, then X.
+        let mut runs = vec![];
+        let mut run: Option<(bool, u8)> = None;
+        for x in 0..size {
+            for z in 0..size {
+                for y in 0..size {
+                    let value = x < nx && y < ny && z < nz && self.is_occupied(x, y, z);
+                    run = match run {
+                        Some((v, count)) if v == value && count < u8::MAX => Some((v, count + 1)),
+                        Some((v, count)) => {
+                            runs.push(v as u8);
+                            runs.push(count);
+                            Some((value, 1))
+                        }
+                        None => Some((value, 1)),
+                    };
+                }
+            }
+        }
+        if let Some((v, count)) = run {
+            runs.push(v as u8);
+            runs.push(count);
+        }
+
+        writer.write_all(&runs)?;
+        Ok(())
+    }
+
+    /// Returns the coordinates of the voxel that contains `p`, clamped to the
+    /// grid.
+    fn voxel_containing(&self, p: Point3<f32>) -> (usize, usize, usize) {
+        let clamp = |value: f32, origin: f32, dimension: usize| {
+            let i = ((value - origin) / self.voxel_size).floor();
+            (i.max(0.0) as usize).min(dimension - 1)
+        };
+        (
+            clamp(p.x, self.origin.x, self.dimensions.0),
+            clamp(p.y, self.origin.y, self.dimensions.1),
+            clamp(p.z, self.origin.z, self.dimensions.2),
+        )
+    }
+
+    /// Fills every empty voxel that cannot be reached from the boundary of the
+    /// grid by moving between empty face-adjacent voxels.
+    fn fill_interior(&mut self) {
+        let (nx, ny, nz) = self.dimensions;
+        let mut is_outside = vec![false; self.len()];
+        let mut queue = VecDeque::new();
+
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let on_boundary =
+                        x == 0 || y == 0 || z == 0 || x == nx - 1 || y == ny - 1 || z == nz - 1;
+                    let index = self.index(x, y, z);
+                    if on_boundary && !self.occupied[index] {
+                        is_outside[index] = true;
+                        queue.push_back((x, y, z));
+                    }
+                }
+            }
+        }
+
+        while let Some((x, y, z)) = queue.pop_front() {
+            let mut neighbors = Vec::with_capacity(6);
+            if x > 0 {
+                neighbors.push((x - 1, y, z));
+            }
+            if x + 1 < nx {
+                neighbors.push((x + 1, y, z));
+            }
+            if y > 0 {
+                neighbors.push((x, y - 1, z));
+            }
+            if y + 1 < ny {
+                neighbors.push((x, y + 1, z));
+            }
+            if z > 0 {
+                neighbors.push((x, y, z - 1));
+            }
+            if z + 1 < nz {
+                neighbors.push((x, y, z + 1));
+            }
+
+            for (x, y, z) in neighbors {
+                let index = self.index(x, y, z);
+                if !self.occupied[index] && !is_outside[index] {
+                    is_outside[index] = true;
+                    queue.push_back((x, y, z));
+                }
+            }
+        }
+
+        for (occupied, outside) in self.occupied.iter_mut().zip(is_outside) {
+            *occupied = !outside;
+        }
+    }
+}
+
+/// Returns true if the triangle overlaps an axis-aligned box that is centered
+/// at the origin and has the given half extents.
+///
+/// This is the separating axis test from Tomas Akenine-Möller's "Fast 3D
+/// Triangle-Box Overlap Testing".
+fn triangle_overlaps_box(
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    c: Vector3<f32>,
+    half: Vector3<f32>,
+) -> bool {
+    let vertices = [a, b, c];
+    let edges = [b - a, c - b, a - c];
+    let box_axes = [
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+    ];
+
+    let separated_on = |axis: Vector3<f32>| {
+        let (min, max) = vertices
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+                let d = v.dot(axis);
+                (lo.min(d), hi.max(d))
+            });
+        let radius = half.x * axis.x.abs() + half.y * axis.y.abs() + half.z * axis.z.abs();
+        min > radius || max < -radius
+    };
+
+    // The cross products of the box's axes with the triangle's edges.
+    for edge in &edges {
+        for box_axis in &box_axes {
+            let axis = box_axis.cross(*edge);
+            if axis.magnitude2() > 0.0 && separated_on(axis) {
+                return false;
+            }
+        }
+    }
+
+    // The box's face normals.
+    if box_axes.iter().any(|&axis| separated_on(axis)) {
+        return false;
+    }
+
+    // The triangle's normal.
+    !separated_on(edges[0].cross(edges[1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MeshBuilder;
+
+    /// Returns whether the voxel is on the outer layer of a cubic grid.
+    fn is_shell(grid: &VoxelGrid, (x, y, z): (usize, usize, usize)) -> bool {
+        let last = grid.dimensions.0 - 1;
+        [x, y, z].iter().any(|&c| c == 0 || c == last)
+    }
+
+    #[test]
+    fn voxelizes_cube_surface_and_solid() {
+        let cube = MeshBuilder::cuboid(Vector3::new(1.0, 1.0, 1.0), 1).build();
+
+        let surface = cube.voxelize(4, VoxelizeMode::Surface).unwrap();
+        assert_eq!(surface.dimensions, (4, 4, 4));
+        assert_eq!(surface.origin, Point3::new(-0.5, -0.5, -0.5));
+        assert_eq!(surface.voxel_size, 0.25);
+        assert_eq!(surface.occupied_count(), 4 * 4 * 4 - 2 * 2 * 2);
+        assert!(surface
+            .occupied_voxels()
+            .all(|voxel| is_shell(&surface, voxel)));
+
+        let solid = cube.voxelize(4, VoxelizeMode::Solid).unwrap();
+        assert_eq!(solid.occupied_count(), 4 * 4 * 4);
+    }
+
+    #[test]
+    fn solid_sphere_is_filled() {
+        let sphere = MeshBuilder::sphere(1.0, 32, 16).build();
+        let surface = sphere.voxelize(10, VoxelizeMode::Surface).unwrap();
+        let solid = sphere.voxelize(10, VoxelizeMode::Solid).unwrap();
+        assert!(!surface.is_occupied(5, 5, 5));
+        assert!(solid.is_occupied(5, 5, 5));
+        assert!(!solid.is_occupied(0, 0, 0));
+        // Every surface voxel is part of the solid.
+        assert!(surface
+            .occupied_voxels()
+            .all(|(x, y, z)| solid.is_occupied(x, y, z)));
+        assert!(solid.occupied_count() > surface.occupied_count());
+    }
+
+    #[test]
+    fn flat_mesh_gets_one_layer() {
+        let plane = MeshBuilder::plane(2.0, 1.0, 1, 1).build();
+        let grid = plane.voxelize(4, VoxelizeMode::Solid).unwrap();
+        assert_eq!(grid.dimensions, (4, 2, 1));
+        assert_eq!(grid.occupied_count(), 8);
+        assert!(Mesh::default().voxelize(4, VoxelizeMode::Surface).is_none());
+    }
+
+    #[test]
+    fn writes_raw_bytes() {
+        let grid = VoxelGrid {
+            dimensions: (3, 1, 1),
+            origin: Point3::new(0.0, 0.0, 0.0),
+            voxel_size: 1.0,
+            occupied: vec![true, false, true],
+        };
+        let mut bytes = vec![];
+        grid.write_raw(&mut bytes).unwrap();
+        assert_eq!(bytes, vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn writes_binvox_header_and_runs() {
+        let grid = VoxelGrid {
+            dimensions: (2, 1, 1),
+            origin: Point3::new(1.0, 2.0, 3.0),
+            voxel_size: 0.5,
+            occupied: vec![false, true],
+        };
+        let mut bytes = vec![];
+        grid.write_binvox(&mut bytes).unwrap();
+
+        let header = "#binvox 1\ndim 2 2 2\ntranslate 1 2 3\nscale 1\ndata\n";
+        assert_eq!(&bytes[..header.len()], header.as_bytes());
+        // The grid is padded to 2×2×2, and Y changes fastest, then Z, then X,
+        // so the only occupied voxel is the fifth.
+        assert_eq!(&bytes[header.len()..], &[0, 4, 1, 1, 0, 3]);
+    }
+
+    #[test]
+    fn splits_long_binvox_runs() {
+        let grid = VoxelGrid {
+            dimensions: (8, 8, 8),
+            origin: Point3::new(0.0, 0.0, 0.0),
+            voxel_size: 1.0,
+            occupied: vec![false; 512],
+        };
+        let mut bytes = vec![];
+        grid.write_binvox(&mut bytes).unwrap();
+        let data = &bytes[bytes.len() - 6..];
+        assert_eq!(data, &[0, 255, 0, 255, 0, 2]);
+    }
+}
@@ -1,29 +1,69 @@
 mod camera;
 mod error;
 mod light;
+mod material;
 mod mesh_buffers;
+mod point_cloud_buffers;
+mod point_pipeline;
+mod point_style;
 mod render_pipeline;
+mod scene;
 mod texture;
 mod transformation;
 
 pub use error::{Error, Result};
+pub use scene::{Scene, SceneNode};
 
 use camera::Camera;
-use cgmath::Matrix4;
 use light::PointLight;
-use mesh::Mesh;
+use material::GpuMaterials;
+use mesh::LevelOfDetail;
 use mesh_buffers::GpuMeshBuffers;
+use point_cloud_buffers::GpuPointCloudBuffers;
+use point_pipeline::{PointCloudInstance, PointPipeline};
+use point_style::PointStyle;
+use render_pipeline::{Frame, MeshInstance, RenderTarget};
 use texture::Texture;
 use transformation::Transformation;
 
 pub struct Config<'a> {
-    pub mesh: &'a Mesh,
+    pub scene: &'a Scene<'a>,
     pub width: u32,
     pub height: u32,
     pub camera_fovy: cgmath::Deg<f32>,
     pub camera_position: cgmath::Point3<f32>,
     pub point_light_position: cgmath::Point3<f32>,
     pub point_light_intensity: f32,
+
+    /// The width in pixels of the disk that is drawn for each point of a
+    /// point cloud.
+    pub point_size: f32,
+}
+
+impl Config<'_> {
+    /// Choose the coarsest level of detail whose error would be at most
+    /// `max_pixel_error` pixels in an image of this config's size, when the
+    /// level is `camera_distance` away from this config's camera. Falls back to
+    /// the finest level if none are accurate enough. Levels should be ordered
+    /// from finest to coarsest, as returned by `Mesh::lod_chain`.
+    ///
+    /// Pixels are square, so the error in pixels is the same horizontally and
+    /// vertically and only the height and vertical field of view are needed.
+    pub fn choose_level_of_detail<'l>(
+        &self,
+        levels: &'l [LevelOfDetail],
+        camera_distance: f32,
+        max_pixel_error: f32,
+    ) -> Option<&'l LevelOfDetail> {
+        let index = mesh::choose_level_of_detail(
+            levels,
+            camera_distance,
+            self.camera_fovy,
+            self.height,
+            max_pixel_error,
+        )?;
+        Some(&levels[index])
+    }
 }
 
 /// Generate a screenshot.
@@ -33,8 +73,6 @@ pub async fn render(config: Config<'_>) -> Result<Vec<u8>> {
     let depth_texture =
         Texture::create_depth_texture(&device, config.width, config.height, "Depth Texture");
 
-    let model_transformation = Transformation::new(&device, Matrix4::from_scale(1.0));
-
     let camera = Camera::new_perspective_camera(
         &device,
         config.camera_position,
@@ -62,29 +100,111 @@ pub async fn render(config: Config<'_>) -> Result<Vec<u8>> {
         "Output Texture",
     );
     let output_buffer = create_output_buffer(&device, config.width, config.height);
+    let model_transformation_bind_group_layout = Transformation::create_bind_group_layout(&device);
+    let material_bind_group_layout = GpuMaterials::create_bind_group_layout(&device);
     let render_pipeline = render_pipeline::RenderPipeline::new(
         &device,
-        &model_transformation.bind_group_layout,
+        &model_transformation_bind_group_layout,
         &camera.bind_group_layout,
         &point_light.bind_group_layout,
+        &material_bind_group_layout,
         depth_texture.desc.format,
         output_texture.desc.format,
     );
-
-    let mesh_buffers = GpuMeshBuffers::load(&device, config.mesh);
-    render_pipeline.render(
+    let point_style = PointStyle::new(&device, config.point_size, config.width, config.height);
+    let point_pipeline = PointPipeline::new(
         &device,
-        &queue,
-        &mesh_buffers,
-        &model_transformation.bind_group,
-        &camera.bind_group,
-        &point_light.bind_group,
-        config.width,
-        config.height,
-        &depth_texture,
-        &output_texture,
-        &output_buffer,
+        &model_transformation_bind_group_layout,
+        &camera.bind_group_layout,
+        &point_light.bind_group_layout,
+        &point_style.bind_group_layout,
+        depth_texture.desc.format,
+        output_texture.desc.format,
     );
+
+    // Meshes that are shared between nodes are uploaded once, and each node
+    // only gets its own model transformation.
+    let meshes = config.scene.unique_meshes();
+    let mesh_buffers: Vec<GpuMeshBuffers> = meshes
+        .iter()
+        .map(|mesh| GpuMeshBuffers::load(&device, mesh))
+        .collect();
+    let materials: Vec<GpuMaterials> = meshes
+        .iter()
+        .map(|mesh| GpuMaterials::load(&device, &material_bind_group_layout, mesh))
+        .collect();
+    let scene_instances = config.scene.instances();
+    let model_transformations: Vec<Transformation> = scene_instances
+        .iter()
+        .map(|(_, world_transformation)| {
+            Transformation::new(
+                &device,
+                &model_transformation_bind_group_layout,
+                *world_transformation,
+            )
+        })
+        .collect();
+    let instances: Vec<MeshInstance> = scene_instances
+        .iter()
+        .zip(&model_transformations)
+        .map(|((mesh, world_transformation), model_transformation)| {
+            let index = meshes.iter().position(|m| std::ptr::eq(*m, *mesh)).unwrap();
+            MeshInstance {
+                mesh: &mesh_buffers[index],
+                materials: &materials[index],
+                model_transformation_bind_group: &model_transformation.bind_group,
+                is_mirrored: mesh::is_mirroring(*world_transformation),
+            }
+        })
+        .collect();
+
+    let point_clouds = config.scene.unique_point_clouds();
+    let point_cloud_buffers: Vec<GpuPointCloudBuffers> = point_clouds
+        .iter()
+        .map(|point_cloud| GpuPointCloudBuffers::load(&device, point_cloud))
+        .collect();
+    let scene_point_cloud_instances = config.scene.point_cloud_instances();
+    let point_cloud_transformations: Vec<Transformation> = scene_point_cloud_instances
+        .iter()
+        .map(|(_, world_transformation)| {
+            Transformation::new(
+                &device,
+                &model_transformation_bind_group_layout,
+                *world_transformation,
+            )
+        })
+        .collect();
+    let point_cloud_instances: Vec<PointCloudInstance> = scene_point_cloud_instances
+        .iter()
+        .zip(&point_cloud_transformations)
+        .map(|((point_cloud, _), model_transformation)| {
+            let index = point_clouds
+                .iter()
+                .position(|c| std::ptr::eq(*c, *point_cloud))
+                .unwrap();
+            PointCloudInstance {
+                point_cloud: &point_cloud_buffers[index],
+                model_transformation_bind_group: &model_transformation.bind_group,
+            }
+        })
+        .collect();
+
+    let frame = Frame {
+        instances: &instances,
+        point_pipeline: &point_pipeline,
+        point_cloud_instances: &point_cloud_instances,
+        point_style_bind_group: &point_style.bind_group,
+        camera_bind_group: &camera.bind_group,
+        point_light_bind_group: &point_light.bind_group,
+    };
+    let target = RenderTarget {
+        width: config.width,
+        height: config.height,
+        depth_texture: &depth_texture,
+        output_texture: &output_texture,
+        output_buffer: &output_buffer,
+    };
+    render_pipeline.render(&device, &queue, &frame, &target);
     poll_from_device_to_buffer(&device, &output_buffer).await?;
 
     let data: Vec<u8> = output_buffer
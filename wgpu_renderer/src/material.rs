@@ -0,0 +1,83 @@
+use mesh::Mesh;
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MaterialUniform {
+    pub base_color: [f32; 4],
+}
+
+/// Contains the uniform buffer and bind group of one material.
+pub struct GpuMaterial {
+    pub uniform: MaterialUniform,
+    pub buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+/// Contains the materials of a mesh. They all share one bind group layout.
+pub struct GpuMaterials {
+    pub materials: Vec<GpuMaterial>,
+}
+
+impl GpuMaterials {
+    /// Load the mesh's materials into uniform buffers, with bind groups that
+    /// have the layout from `create_bind_group_layout`. A mesh that does not
+    /// assign materials to its triangles gets a single white material.
+    pub fn load(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        mesh: &Mesh,
+    ) -> Self {
+        let base_colors = if mesh.triangle_materials.is_some() {
+            mesh.materials.iter().map(|m| m.base_color.into()).collect()
+        } else {
+            vec![[1.0, 1.0, 1.0, 1.0]]
+        };
+
+        let materials = base_colors
+            .into_iter()
+            .map(|base_color| {
+                let uniform = MaterialUniform { base_color };
+                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Material Uniform Buffer"),
+                    contents: bytemuck::cast_slice(&[uniform]),
+                    usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+                });
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }],
+                    label: Some("Material Bind Group"),
+                });
+                GpuMaterial {
+                    uniform,
+                    buffer,
+                    bind_group,
+                }
+            })
+            .collect();
+
+        Self { materials }
+    }
+
+    /// Create the bind group layout of a material. Every mesh's materials have
+    /// an identical layout, so a pipeline created with this layout can use the
+    /// materials of any mesh.
+    pub fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Material Bind Group Layout"),
+        })
+    }
+}
@@ -1,5 +1,7 @@
 // use anyhow::*;
+use cgmath::Vector4;
 use mesh::Mesh;
+use std::ops::Range;
 use wgpu::util::DeviceExt;
 
 pub trait DescribeBufferLayout {
@@ -11,6 +13,7 @@ pub trait DescribeBufferLayout {
 pub struct GpuVertex {
     position: [f32; 3],
     normal: [f32; 3],
+    color: [f32; 4],
 }
 
 impl DescribeBufferLayout for GpuVertex {
@@ -32,6 +35,12 @@ impl DescribeBufferLayout for GpuVertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float3,
                 },
+                // Color
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float4,
+                },
             ],
         }
     }
@@ -43,23 +52,49 @@ pub struct GpuMeshBuffers {
     pub name: String,
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
-    pub num_indices: u32,
+
+    /// The range of indices that is drawn with each material, paired with the
+    /// material's index. Triangles are sorted by material so that each
+    /// material's triangles are contiguous.
+    pub material_ranges: Vec<(usize, Range<u32>)>,
 }
 
 impl GpuMeshBuffers {
-    /// Load the mesh into GPU-accessible buffers.
+    /// Load the mesh into GPU-accessible buffers. Vertices are white if the
+    /// mesh does not contain colors. A mesh that does not assign materials to
+    /// its triangles is drawn with a single material at index 0.
     pub fn load(device: &wgpu::Device, mesh: &Mesh) -> Self {
         let vertices: Vec<GpuVertex> = mesh
             .positions
             .iter()
             .zip(mesh.normals.iter())
-            .map(|(p, n)| GpuVertex {
-                position: [p.x, p.y, p.z],
-                normal: [n.x, n.y, n.z],
+            .enumerate()
+            .map(|(i, (p, n))| {
+                let c = mesh
+                    .colors
+                    .as_ref()
+                    .map_or(Vector4::new(1.0, 1.0, 1.0, 1.0), |colors| colors[i]);
+                GpuVertex {
+                    position: [p.x, p.y, p.z],
+                    normal: [n.x, n.y, n.z],
+                    color: [c.x, c.y, c.z, c.w],
+                }
             })
             .collect();
 
-        let num_elements = vertices.len() as u32;
+        let mut indices: Vec<u32> = Vec::with_capacity(3 * mesh.triangle_vertex_indices.len());
+        let mut material_ranges = vec![];
+        for (material, triangles) in mesh.material_triangle_indices().iter().enumerate() {
+            let start = indices.len() as u32;
+            for &t in triangles {
+                let (i1, i2, i3) = mesh.triangle_vertex_indices[t];
+                indices.extend_from_slice(&[i1 as u32, i2 as u32, i3 as u32]);
+            }
+            if !triangles.is_empty() {
+                material_ranges.push((material, start..indices.len() as u32));
+            }
+        }
+
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
             contents: bytemuck::cast_slice(&vertices),
@@ -67,7 +102,7 @@ impl GpuMeshBuffers {
         });
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(&(0..num_elements).collect::<Vec<u32>>()),
+            contents: bytemuck::cast_slice(&indices),
             usage: wgpu::BufferUsage::INDEX,
         });
 
@@ -75,7 +110,7 @@ impl GpuMeshBuffers {
             name: "Mesh".to_string(),
             vertex_buffer,
             index_buffer,
-            num_indices: num_elements,
+            material_ranges,
         }
     }
 }
@@ -0,0 +1,90 @@
+use super::mesh_buffers::DescribeBufferLayout;
+use cgmath::{Vector3, Vector4};
+use mesh::PointCloud;
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuPoint {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 4],
+}
+
+impl DescribeBufferLayout for GpuPoint {
+    /// Points are per-instance data, since each point is drawn as an instance
+    /// of a quad.
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<GpuPoint>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Instance,
+            attributes: &[
+                // Position
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float3,
+                },
+                // Normal
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float3,
+                },
+                // Color
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float4,
+                },
+            ],
+        }
+    }
+}
+
+/// Contains GPU-accessible buffers for a point cloud.
+#[derive(Debug)]
+pub struct GpuPointCloudBuffers {
+    pub point_buffer: wgpu::Buffer,
+    pub num_points: u32,
+}
+
+impl GpuPointCloudBuffers {
+    /// Load the point cloud into GPU-accessible buffers. Points are white if
+    /// the cloud does not contain colors. Points without normals get zero
+    /// normals, which the shader draws unlit.
+    pub fn load(device: &wgpu::Device, point_cloud: &PointCloud) -> Self {
+        let points: Vec<GpuPoint> = point_cloud
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let n = point_cloud
+                    .normals
+                    .as_ref()
+                    .map_or(Vector3::new(0.0, 0.0, 0.0), |normals| normals[i]);
+                let c = point_cloud
+                    .colors
+                    .as_ref()
+                    .map_or(Vector4::new(1.0, 1.0, 1.0, 1.0), |colors| colors[i]);
+                GpuPoint {
+                    position: [p.x, p.y, p.z],
+                    normal: [n.x, n.y, n.z],
+                    color: [c.x, c.y, c.z, c.w],
+                }
+            })
+            .collect();
+
+        let point_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Point Buffer"),
+            contents: bytemuck::cast_slice(&points),
+            usage: wgpu::BufferUsage::VERTEX,
+        });
+
+        Self {
+            point_buffer,
+            num_points: points.len() as u32,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::mesh_buffers::DescribeBufferLayout;
+use super::point_cloud_buffers;
+
+/// A point cloud drawn with a model transformation. Several instances may
+/// share the same point buffer.
+pub struct PointCloudInstance<'a> {
+    pub point_cloud: &'a point_cloud_buffers::GpuPointCloudBuffers,
+    pub model_transformation_bind_group: &'a wgpu::BindGroup,
+}
+
+/// Draws each point of a point cloud as a disk of a fixed size in pixels.
+pub struct PointPipeline {
+    pub pipeline: wgpu::RenderPipeline,
+}
+
+impl PointPipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        model_transformation_bind_group_layout: &wgpu::BindGroupLayout,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        point_light_bind_group_layout: &wgpu::BindGroupLayout,
+        point_style_bind_group_layout: &wgpu::BindGroupLayout,
+        depth_texture_format: wgpu::TextureFormat,
+        output_texture_format: wgpu::TextureFormat,
+    ) -> Self {
+        let vert_shader_module =
+            device.create_shader_module(&wgpu::include_spirv!("shader.vert.spv"));
+        let frag_shader_module =
+            device.create_shader_module(&wgpu::include_spirv!("shader.frag.spv"));
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Point Pipeline Layout"),
+            bind_group_layouts: &[
+                model_transformation_bind_group_layout,
+                camera_bind_group_layout,
+                point_light_bind_group_layout,
+                point_style_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Point Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &vert_shader_module,
+                entry_point: "main",
+                buffers: &[point_cloud_buffers::GpuPoint::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &frag_shader_module,
+                entry_point: "main",
+                targets: &[wgpu::ColorTargetState {
+                    format: output_texture_format,
+                    alpha_blend: wgpu::BlendState::REPLACE,
+                    color_blend: wgpu::BlendState::REPLACE,
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                // Each point is a quad of four vertices that the vertex shader
+                // generates from the vertex index.
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // Points face the camera, so there are no back faces to cull.
+                cull_mode: wgpu::CullMode::None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_texture_format,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+                clamp_depth: false,
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        });
+
+        Self { pipeline }
+    }
+
+    /// Record commands that draw each point cloud instance into the render
+    /// pass. The camera and point light must already be bound to groups 1 and
+    /// 2.
+    pub fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        instances: &[PointCloudInstance<'a>],
+        point_style_bind_group: &'a wgpu::BindGroup,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(3, point_style_bind_group, &[]);
+        for instance in instances {
+            let point_cloud = instance.point_cloud;
+            render_pass.set_vertex_buffer(0, point_cloud.point_buffer.slice(..));
+            render_pass.set_bind_group(0, instance.model_transformation_bind_group, &[]);
+            render_pass.draw(0..4, 0..point_cloud.num_points);
+        }
+    }
+}
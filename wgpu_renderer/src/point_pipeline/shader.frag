@@ -0,0 +1,50 @@
+#version 450
+
+layout(set=1, binding=0)
+uniform Camera {
+    vec3 u_view_position;
+    mat4 u_view_proj; // unused
+};
+
+layout(set = 2, binding = 0) uniform Light {
+    vec3 light_position;
+    vec3 light_color;
+};
+
+layout(location=0) in vec2 v_corner;
+layout(location=1) in vec3 v_normal;
+layout(location=2) in vec3 v_position;
+layout(location=3) in vec4 v_color;
+
+layout(location=0) out vec4 f_color;
+
+void main() {
+    // Round each quad into a disk.
+    if (dot(v_corner, v_corner) > 1.0) {
+        discard;
+    }
+
+    // Points without normals can't be lit, so they are drawn in their own
+    // color.
+    if (dot(v_normal, v_normal) == 0.0) {
+        f_color = v_color;
+        return;
+    }
+
+    vec3 normal = normalize(v_normal);
+    vec3 light_dir = normalize(light_position - v_position);
+
+    float ambient_strength = 0.05;
+    vec3 ambient_color = light_color * ambient_strength;
+
+    float diffuse_strength = max(dot(normal, light_dir), 0.0);
+    vec3 diffuse_color = light_color * diffuse_strength;
+
+    vec3 view_dir = normalize(u_view_position - v_position);
+    vec3 reflect_dir = reflect(-light_dir, normal);
+    float specular_strength = pow(max(dot(view_dir, reflect_dir), 0.0), 32);
+    vec3 specular_color = specular_strength * light_color;
+
+    vec3 result = (ambient_color + diffuse_color + specular_color) * v_color.xyz;
+    f_color = vec4(result, v_color.a);
+}
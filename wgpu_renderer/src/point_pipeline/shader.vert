@@ -0,0 +1,45 @@
+#version 450
+
+layout(set=0, binding=0)
+uniform ModelTransformation {
+    mat4 u_model_transf;
+};
+
+layout(set=1, binding=0)
+uniform Camera {
+    vec3 u_view_position; // unused
+    mat4 u_view_proj;
+};
+
+layout(set=3, binding=0)
+uniform PointStyle {
+    vec2 u_viewport_size;
+    float u_point_size;
+};
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec3 a_normal;
+layout(location=2) in vec4 a_color;
+
+layout(location=0) out vec2 v_corner;
+layout(location=1) out vec3 v_normal;
+layout(location=2) out vec3 v_position;
+layout(location=3) out vec4 v_color;
+
+void main() {
+    // Each point is a quad drawn as a triangle strip of four vertices.
+    v_corner = vec2(float(gl_VertexIndex & 1), float(gl_VertexIndex >> 1)) * 2.0 - 1.0;
+
+    mat3 normal_matrix = mat3(transpose(inverse(u_model_transf)));
+    v_normal = normal_matrix * a_normal;
+
+    vec4 world_position = u_model_transf * vec4(a_position, 1.0);
+    v_position = world_position.xyz;
+    v_color = a_color;
+
+    // Move the corner in clip space, scaled by w, so that the quad is
+    // `u_point_size` pixels wide at any depth.
+    vec4 clip_position = u_view_proj * world_position;
+    clip_position.xy += v_corner * u_point_size / u_viewport_size * clip_position.w;
+    gl_Position = clip_position;
+}
@@ -0,0 +1,64 @@
+use wgpu::util::DeviceExt;
+
+/// Uniform data that can be sent to the shaders. Contains the size of the
+/// viewport and the size of each point, both in pixels.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PointStyleUniform {
+    pub viewport_size: [f32; 2],
+    pub point_size: f32,
+    // Uniforms must be 16 bytes wide, so we need to add 4 bytes of padding.
+    _padding: u32,
+}
+
+pub struct PointStyle {
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl PointStyle {
+    /// Create the uniform that draws each point as a disk that is `point_size`
+    /// pixels wide in a viewport of the given size.
+    pub fn new(device: &wgpu::Device, point_size: f32, width: u32, height: u32) -> Self {
+        let uniform = PointStyleUniform {
+            viewport_size: [width as f32, height as f32],
+            point_size,
+            _padding: 0,
+        };
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Point Style Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Point Style Bind Group Layout"),
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("Point Style Bind Group"),
+        });
+
+        // The bind group keeps the buffer alive, so it isn't stored.
+        Self {
+            bind_group_layout,
+            bind_group,
+        }
+    }
+}
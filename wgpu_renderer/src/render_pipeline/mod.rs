@@ -1,9 +1,47 @@
+use super::material;
 use super::mesh_buffers;
 use super::mesh_buffers::DescribeBufferLayout;
+use super::point_pipeline;
 use super::texture;
 
+/// A mesh drawn with a model transformation. Several instances may share the
+/// same mesh buffers and materials.
+pub struct MeshInstance<'a> {
+    pub mesh: &'a mesh_buffers::GpuMeshBuffers,
+    pub materials: &'a material::GpuMaterials,
+    pub model_transformation_bind_group: &'a wgpu::BindGroup,
+
+    /// Whether the model transformation mirrors the mesh, which reverses the
+    /// winding of its triangles on screen.
+    pub is_mirrored: bool,
+}
+
+/// Everything that is drawn in a frame, along with the bind groups that are
+/// shared by every instance.
+pub struct Frame<'a> {
+    pub instances: &'a [MeshInstance<'a>],
+    pub point_pipeline: &'a point_pipeline::PointPipeline,
+    pub point_cloud_instances: &'a [point_pipeline::PointCloudInstance<'a>],
+    pub point_style_bind_group: &'a wgpu::BindGroup,
+    pub camera_bind_group: &'a wgpu::BindGroup,
+    pub point_light_bind_group: &'a wgpu::BindGroup,
+}
+
+/// The textures that a frame is drawn into and the buffer that the output
+/// texture is copied to.
+pub struct RenderTarget<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub depth_texture: &'a texture::Texture<'a>,
+    pub output_texture: &'a texture::Texture<'a>,
+    pub output_buffer: &'a wgpu::Buffer,
+}
+
 pub struct RenderPipeline {
     pub pipeline: wgpu::RenderPipeline,
+
+    /// Draws mirrored instances, whose front faces are wound clockwise.
+    pub mirrored_pipeline: wgpu::RenderPipeline,
 }
 
 impl RenderPipeline {
@@ -12,6 +50,7 @@ impl RenderPipeline {
         model_transformation_bind_group_layout: &wgpu::BindGroupLayout,
         camera_bind_group_layout: &wgpu::BindGroupLayout,
         point_light_bind_group_layout: &wgpu::BindGroupLayout,
+        material_bind_group_layout: &wgpu::BindGroupLayout,
         depth_texture_format: wgpu::TextureFormat,
         output_texture_format: wgpu::TextureFormat,
     ) -> Self {
@@ -26,71 +65,72 @@ impl RenderPipeline {
                 model_transformation_bind_group_layout,
                 camera_bind_group_layout,
                 point_light_bind_group_layout,
+                material_bind_group_layout,
             ],
             push_constant_ranges: &[],
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&layout),
-            vertex: wgpu::VertexState {
-                module: &vert_shader_module,
-                entry_point: "main",
-                buffers: &[mesh_buffers::GpuVertex::desc()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &frag_shader_module,
-                entry_point: "main",
-                targets: &[wgpu::ColorTargetState {
-                    format: output_texture_format,
-                    alpha_blend: wgpu::BlendState::REPLACE,
-                    color_blend: wgpu::BlendState::REPLACE,
-                    write_mask: wgpu::ColorWrite::ALL,
-                }],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: wgpu::CullMode::Back,
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE.
-                polygon_mode: wgpu::PolygonMode::Fill,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: depth_texture_format,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                // Stencil and depth buffers are often stored together. We aren't using stencil here.
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-                // Setting this to true requires Features::DEPTH_CLAMPING
-                clamp_depth: false,
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-        });
+        let create_pipeline = |label, front_face| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &vert_shader_module,
+                    entry_point: "main",
+                    buffers: &[mesh_buffers::GpuVertex::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &frag_shader_module,
+                    entry_point: "main",
+                    targets: &[wgpu::ColorTargetState {
+                        format: output_texture_format,
+                        alpha_blend: wgpu::BlendState::REPLACE,
+                        color_blend: wgpu::BlendState::REPLACE,
+                        write_mask: wgpu::ColorWrite::ALL,
+                    }],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face,
+                    cull_mode: wgpu::CullMode::Back,
+                    // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE.
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: depth_texture_format,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    // Stencil and depth buffers are often stored together. We aren't using stencil here.
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                    // Setting this to true requires Features::DEPTH_CLAMPING
+                    clamp_depth: false,
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+            })
+        };
 
-        Self { pipeline }
+        Self {
+            pipeline: create_pipeline("Render Pipeline", wgpu::FrontFace::Ccw),
+            mirrored_pipeline: create_pipeline("Mirrored Render Pipeline", wgpu::FrontFace::Cw),
+        }
     }
 
-    /// Execute the `render_pipeline`, writing output to the `output_texture`. Then
-    /// copy the texture to the `output_buffer`.
+    /// Execute the `render_pipeline`, drawing each mesh instance of the frame
+    /// and then each point cloud instance with the frame's `point_pipeline`,
+    /// and writing output to the target's `output_texture`. Then copy the
+    /// texture to the target's `output_buffer`.
     pub fn render(
         &self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        mesh: &mesh_buffers::GpuMeshBuffers,
-        model_transformation_bind_group: &wgpu::BindGroup,
-        camera_bind_group: &wgpu::BindGroup,
-        point_light_bind_group: &wgpu::BindGroup,
-        screenshot_width: u32,
-        screenshot_height: u32,
-        depth_texture: &texture::Texture,
-        output_texture: &texture::Texture,
-        output_buffer: &wgpu::Buffer,
+        frame: &Frame,
+        target: &RenderTarget,
     ) {
         let mut encoder =
             device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
@@ -99,7 +139,7 @@ impl RenderPipeline {
             let render_pass_desc = wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                    attachment: &output_texture.view,
+                    attachment: &target.output_texture.view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
@@ -112,7 +152,7 @@ impl RenderPipeline {
                     },
                 }],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
-                    attachment: &depth_texture.view,
+                    attachment: &target.depth_texture.view,
                     depth_ops: Some(wgpu::Operations {
                         load: wgpu::LoadOp::Clear(1.0),
                         store: true,
@@ -121,30 +161,55 @@ impl RenderPipeline {
                 }),
             };
             let mut render_pass = encoder.begin_render_pass(&render_pass_desc);
-            render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, model_transformation_bind_group, &[]);
-            render_pass.set_bind_group(1, camera_bind_group, &[]);
-            render_pass.set_bind_group(2, point_light_bind_group, &[]);
-            render_pass.draw(0..mesh.num_indices, 0..1);
+            render_pass.set_bind_group(1, frame.camera_bind_group, &[]);
+            render_pass.set_bind_group(2, frame.point_light_bind_group, &[]);
+            for instance in frame.instances {
+                // Both pipelines have the same layout, so switching between
+                // them keeps the bind groups that are already set.
+                if instance.is_mirrored {
+                    render_pass.set_pipeline(&self.mirrored_pipeline);
+                } else {
+                    render_pass.set_pipeline(&self.pipeline);
+                }
+                let mesh = instance.mesh;
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.set_bind_group(0, instance.model_transformation_bind_group, &[]);
+                for (material, indices) in &mesh.material_ranges {
+                    render_pass.set_bind_group(
+                        3,
+                        &instance.materials.materials[*material].bind_group,
+                        &[],
+                    );
+                    render_pass.draw_indexed(indices.clone(), 0, 0..1);
+                }
+            }
+            if !frame.point_cloud_instances.is_empty() {
+                frame.point_pipeline.draw(
+                    &mut render_pass,
+                    frame.point_cloud_instances,
+                    frame.point_style_bind_group,
+                );
+            }
         }
 
         let u32_size = std::mem::size_of::<u32>() as u32;
         encoder.copy_texture_to_buffer(
             wgpu::TextureCopyView {
-                texture: &output_texture.texture,
+                texture: &target.output_texture.texture,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
             },
             wgpu::BufferCopyView {
-                buffer: &output_buffer,
+                buffer: target.output_buffer,
                 layout: wgpu::TextureDataLayout {
                     offset: 0,
-                    bytes_per_row: u32_size * screenshot_width,
-                    rows_per_image: screenshot_height,
+                    bytes_per_row: u32_size * target.width,
+                    rows_per_image: target.height,
                 },
             },
-            output_texture.desc.size,
+            target.output_texture.desc.size,
         );
 
         queue.submit(Some(encoder.finish()));
@@ -13,16 +13,19 @@ uniform Camera {
 
 layout(location=0) in vec3 a_position;
 layout(location=1) in vec3 a_normal;
+layout(location=2) in vec4 a_color;
 
 layout(location=1) out vec3 v_normal;
 layout(location=2) out vec3 v_position;
+layout(location=3) out vec4 v_color;
 
 void main() {
     // TODO: This matrix math should be pulled out of the shader.
     mat3 normal_matrix = mat3(transpose(inverse(u_model_transf)));
     v_normal = normal_matrix * a_normal;
 
-    v_position = a_position;
+    v_position = vec3(u_model_transf * vec4(a_position, 1.0));
+    v_color = a_color;
 
     gl_Position = u_view_proj * u_model_transf * vec4(a_position, 1.0);
 }
\ No newline at end of file
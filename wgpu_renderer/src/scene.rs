@@ -0,0 +1,256 @@
+use cgmath::{Matrix4, Point3, SquareMatrix, Transform};
+use mesh::{Mesh, PointCloud};
+
+/// A node in a scene. Each node has a transformation relative to its parent
+/// and may draw a mesh and a point cloud and contain child nodes.
+#[derive(Debug, Clone)]
+pub struct SceneNode<'a> {
+    /// The transformation from this node's space to its parent's space.
+    pub transform: Matrix4<f32>,
+
+    /// The mesh to draw with this node's world transformation. Several nodes
+    /// may refer to the same mesh to draw instances of it.
+    pub mesh: Option<&'a Mesh>,
+
+    /// The point cloud to draw with this node's world transformation. Several
+    /// nodes may refer to the same point cloud to draw instances of it.
+    pub point_cloud: Option<&'a PointCloud>,
+
+    pub children: Vec<SceneNode<'a>>,
+}
+
+impl<'a> SceneNode<'a> {
+    /// Create a node with the given transformation and no mesh or children.
+    pub fn new(transform: Matrix4<f32>) -> Self {
+        Self {
+            transform,
+            mesh: None,
+            point_cloud: None,
+            children: vec![],
+        }
+    }
+
+    /// Create a node that draws the mesh with the given transformation.
+    pub fn with_mesh(mesh: &'a Mesh, transform: Matrix4<f32>) -> Self {
+        Self {
+            transform,
+            mesh: Some(mesh),
+            point_cloud: None,
+            children: vec![],
+        }
+    }
+
+    /// Create a node that draws the point cloud with the given transformation.
+    pub fn with_point_cloud(point_cloud: &'a PointCloud, transform: Matrix4<f32>) -> Self {
+        Self {
+            transform,
+            mesh: None,
+            point_cloud: Some(point_cloud),
+            children: vec![],
+        }
+    }
+
+    /// Add a child node.
+    pub fn child(mut self, child: SceneNode<'a>) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+/// A hierarchy of nodes that place meshes in the world.
+#[derive(Debug, Clone, Default)]
+pub struct Scene<'a> {
+    /// The nodes at the top of the hierarchy. Their transformations are
+    /// relative to the world.
+    pub nodes: Vec<SceneNode<'a>>,
+}
+
+impl<'a> Scene<'a> {
+    /// Create an empty scene.
+    pub fn new() -> Self {
+        Self { nodes: vec![] }
+    }
+
+    /// Create a scene that draws a single mesh without transforming it.
+    pub fn from_mesh(mesh: &'a Mesh) -> Self {
+        Self {
+            nodes: vec![SceneNode::with_mesh(mesh, Matrix4::identity())],
+        }
+    }
+
+    /// Add a node at the top of the hierarchy.
+    pub fn add(&mut self, node: SceneNode<'a>) {
+        self.nodes.push(node);
+    }
+
+    /// Create a scene that draws a single point cloud without transforming it.
+    pub fn from_point_cloud(point_cloud: &'a PointCloud) -> Self {
+        Self {
+            nodes: vec![SceneNode::with_point_cloud(
+                point_cloud,
+                Matrix4::identity(),
+            )],
+        }
+    }
+
+    /// Return each mesh in the scene paired with its world transformation, in
+    /// depth-first order.
+    ///
+    /// A world transformation may mirror its mesh, such as the left half of a
+    /// symmetric assembly that reuses the mesh of the right half with a
+    /// negative scale. Mirroring reverses the winding of the mesh's
+    /// triangles, so instances whose transformation [`mesh::is_mirroring`] are
+    /// drawn with clockwise front faces instead of being rendered inside out.
+    /// A node below two mirroring nodes is not mirrored.
+    pub fn instances(&self) -> Vec<(&'a Mesh, Matrix4<f32>)> {
+        let mut instances = vec![];
+        self.visit(|node, world_transform| {
+            if let Some(mesh) = node.mesh {
+                instances.push((mesh, world_transform));
+            }
+        });
+        instances
+    }
+
+    /// Return each point cloud in the scene paired with its world
+    /// transformation, in depth-first order.
+    pub fn point_cloud_instances(&self) -> Vec<(&'a PointCloud, Matrix4<f32>)> {
+        let mut instances = vec![];
+        self.visit(|node, world_transform| {
+            if let Some(point_cloud) = node.point_cloud {
+                instances.push((point_cloud, world_transform));
+            }
+        });
+        instances
+    }
+
+    /// Return each distinct mesh in the scene once, in order of first use.
+    /// Meshes are distinct if they are different objects in memory, even if
+    /// their contents are equal.
+    pub fn unique_meshes(&self) -> Vec<&'a Mesh> {
+        unique(self.instances().into_iter().map(|(mesh, _)| mesh))
+    }
+
+    /// Return each distinct point cloud in the scene once, in order of first
+    /// use. Point clouds are distinct if they are different objects in memory.
+    pub fn unique_point_clouds(&self) -> Vec<&'a PointCloud> {
+        unique(
+            self.point_cloud_instances()
+                .into_iter()
+                .map(|(point_cloud, _)| point_cloud),
+        )
+    }
+
+    /// Return the minimum and maximum corners of an axis-aligned bounding box
+    /// around every mesh and point cloud instance in world space, or `None` if
+    /// the scene contains no vertices or points.
+    pub fn bounding_box(&self) -> Option<(Point3<f32>, Point3<f32>)> {
+        let mut bounds: Option<(Point3<f32>, Point3<f32>)> = None;
+        self.visit(|node, world_transform| {
+            let mesh_positions = node.mesh.map_or(&[][..], |mesh| &mesh.positions);
+            let point_cloud_positions = node
+                .point_cloud
+                .map_or(&[][..], |point_cloud| &point_cloud.positions);
+            for p in mesh_positions.iter().chain(point_cloud_positions) {
+                let p = world_transform.transform_point(*p);
+                bounds = Some(match bounds {
+                    None => (p, p),
+                    Some((min, max)) => (
+                        Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                        Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+                    ),
+                });
+            }
+        });
+        bounds
+    }
+
+    /// Call `f` with each node in the scene and its world transformation, in
+    /// depth-first order.
+    fn visit<F>(&self, mut f: F)
+    where
+        F: FnMut(&SceneNode<'a>, Matrix4<f32>),
+    {
+        let mut stack: Vec<(&SceneNode<'a>, Matrix4<f32>)> = self
+            .nodes
+            .iter()
+            .rev()
+            .map(|node| (node, node.transform))
+            .collect();
+
+        while let Some((node, world_transform)) = stack.pop() {
+            f(node, world_transform);
+            for child in node.children.iter().rev() {
+                stack.push((child, world_transform * child.transform));
+            }
+        }
+    }
+}
+
+/// Return each distinct item once, in order of first appearance. Items are
+/// distinct if they are different objects in memory.
+fn unique<'a, T>(items: impl Iterator<Item = &'a T>) -> Vec<&'a T> {
+    let mut unique: Vec<&'a T> = vec![];
+    for item in items {
+        if !unique.iter().any(|&u| std::ptr::eq(u, item)) {
+            unique.push(item);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Vector3;
+    use mesh::MeshBuilder;
+
+    #[test]
+    fn instances_compose_parent_transformations() {
+        let mesh = MeshBuilder::cuboid(Vector3::new(1.0, 1.0, 1.0), 1).build();
+        let parent = Matrix4::from_translation(Vector3::new(1.0, 0.0, 0.0));
+        let child = Matrix4::from_scale(2.0);
+        let mut scene = Scene::new();
+        scene.add(SceneNode::new(parent).child(SceneNode::with_mesh(&mesh, child)));
+
+        let instances = scene.instances();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].1, parent * child);
+        assert_eq!(
+            scene.bounding_box(),
+            Some((Point3::new(0.0, -1.0, -1.0), Point3::new(2.0, 1.0, 1.0)))
+        );
+    }
+
+    #[test]
+    fn shared_meshes_are_unique_once() {
+        let mesh = MeshBuilder::cuboid(Vector3::new(1.0, 1.0, 1.0), 1).build();
+        let mut scene = Scene::from_mesh(&mesh);
+        scene.add(SceneNode::with_mesh(
+            &mesh,
+            Matrix4::from_translation(Vector3::new(3.0, 0.0, 0.0)),
+        ));
+        assert_eq!(scene.instances().len(), 2);
+        assert_eq!(scene.unique_meshes().len(), 1);
+    }
+
+    #[test]
+    fn mirrored_instances_are_detected() {
+        let mesh = MeshBuilder::cuboid(Vector3::new(1.0, 1.0, 1.0), 1).build();
+        let mirror = Matrix4::from_nonuniform_scale(-1.0, 1.0, 1.0);
+        let mut scene = Scene::from_mesh(&mesh);
+        scene.add(
+            SceneNode::with_mesh(&mesh, mirror)
+                .child(SceneNode::with_mesh(&mesh, Matrix4::from_scale(2.0)))
+                .child(SceneNode::with_mesh(&mesh, mirror)),
+        );
+
+        let mirrored: Vec<bool> = scene
+            .instances()
+            .iter()
+            .map(|(_, world_transform)| mesh::is_mirroring(*world_transform))
+            .collect();
+        // The last node is mirrored twice, which cancels out.
+        assert_eq!(mirrored, vec![false, true, true, false]);
+    }
+}
@@ -29,12 +29,17 @@ pub struct Transformation {
     // TODO: uniform and TransformationUniform could probably be private
     pub uniform: TransformationUniform,
     pub buffer: wgpu::Buffer,
-    pub bind_group_layout: wgpu::BindGroupLayout,
     pub bind_group: wgpu::BindGroup,
 }
 
 impl Transformation {
-    pub fn new(device: &wgpu::Device, transformation: Matrix4<f32>) -> Self {
+    /// Create a uniform buffer that holds the transformation, with a bind
+    /// group that has the layout from `create_bind_group_layout`.
+    pub fn new(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        transformation: Matrix4<f32>,
+    ) -> Self {
         let mut uniform = TransformationUniform::new();
         uniform.update(transformation);
 
@@ -44,22 +49,8 @@ impl Transformation {
             usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
         });
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStage::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-            label: Some("Model Transformation Bind Group Layout"),
-        });
-
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
+            layout: bind_group_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
                 resource: buffer.as_entire_binding(),
@@ -70,8 +61,26 @@ impl Transformation {
         Self {
             uniform,
             buffer,
-            bind_group_layout,
             bind_group,
         }
     }
+
+    /// Create the bind group layout of a model transformation. Every
+    /// transformation has an identical layout, so a pipeline created with this
+    /// layout can use the bind group of any transformation.
+    pub fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Model Transformation Bind Group Layout"),
+        })
+    }
 }
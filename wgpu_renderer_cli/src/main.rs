@@ -1,17 +1,21 @@
 mod error;
 
-use cgmath::{point2, point3, Deg, InnerSpace, Matrix4, Point2, Point3, Rad, Transform};
+use cgmath::{
+    point2, point3, Deg, InnerSpace, Matrix4, Point2, Point3, Rad, SquareMatrix, Transform, Vector4,
+};
 use error::{Error, Result};
 use image::{imageops, ImageBuffer, Rgba};
-use mesh::{Mesh, MeshBuilder};
+use mesh::{AmbientOcclusionSettings, DefectSettings, Heightmap, Mesh, MeshBuilder, PointCloud};
 use std::cmp;
-use std::io::BufReader;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use wgpu_renderer::{Scene, SceneNode};
 
 fn main() -> Result<()> {
     let matches = clap::App::new("Part Viewer")
         .arg(
             clap::Arg::with_name("INPUT")
-                .help("The input STL file to use")
+                .help("The input STL or native binary (.rmsh) mesh file, PLY or XYZ point cloud file, or grayscale heightmap image to use")
                 .required(true)
                 .index(1),
         )
@@ -74,6 +78,50 @@ fn main() -> Result<()> {
                 .short("c")
                 .help("Enables cropping"),
         )
+        .arg(
+            clap::Arg::with_name("ADDITIONAL INPUT")
+                .short("a")
+                .long("add")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("An additional mesh or point cloud file to render together with the input. May be given more than once."),
+        )
+        .arg(
+            clap::Arg::with_name("MESH OUTPUT")
+                .long("write-mesh")
+                .takes_value(true)
+                .help("Also write the loaded mesh in the native binary format, so that later renders can load it faster"),
+        )
+        .arg(
+            clap::Arg::with_name("AMBIENT OCCLUSION SAMPLES")
+                .long("ambient-occlusion")
+                .takes_value(true)
+                .help("Darken crevices with ambient occlusion baked from the given number of rays per vertex"),
+        )
+        .arg(
+            clap::Arg::with_name("DEVIATION REFERENCE")
+                .long("deviation-from")
+                .takes_value(true)
+                .help("Color the mesh by its signed deviation from the given reference mesh file, from blue inside the reference to red outside"),
+        )
+        .arg(
+            clap::Arg::with_name("HIGHLIGHT DEFECTS")
+                .long("highlight-defects")
+                .help("Color the triangles that intersect other triangles or are badly shaped red"),
+        )
+        .arg(
+            clap::Arg::with_name("HEIGHTMAP HEIGHT")
+                .long("heightmap-height")
+                .takes_value(true)
+                .help("The height of a white pixel in heightmap images, where each pixel is one unit wide. Default is a tenth of the image's larger dimension."),
+        )
+        .arg(
+            clap::Arg::with_name("POINT SIZE")
+                .long("point-size")
+                .takes_value(true)
+                .help("The width in pixels of each point of a point cloud. Default is 2."),
+        )
         .get_matches();
 
     // The first four arguments are required by Clap, so unwrapping them is ok.
@@ -107,17 +155,57 @@ fn main() -> Result<()> {
         .unwrap_or("1.0")
         .parse::<f32>()?;
     let is_crop_on = matches.is_present("CROP");
+    let ambient_occlusion_samples = matches
+        .value_of("AMBIENT OCCLUSION SAMPLES")
+        .map(|samples| samples.parse::<usize>())
+        .transpose()?;
+    let heightmap_height = matches
+        .value_of("HEIGHTMAP HEIGHT")
+        .map(|height| height.parse::<f32>())
+        .transpose()?;
+    let point_size = matches
+        .value_of("POINT SIZE")
+        .unwrap_or("2")
+        .parse::<f32>()?;
 
-    let file = std::fs::File::open(&src_path)?;
-    let mut reader = BufReader::new(&file);
-    let mut mesh = MeshBuilder::from_stl(&mut reader)?.build();
+    // Meshes are merged into one mesh and point clouds into one point cloud.
+    let mut mesh = Mesh::default();
+    let mut point_cloud = PointCloud::default();
+    let additional_src_paths = matches.values_of("ADDITIONAL INPUT").into_iter().flatten();
+    for path in std::iter::once(src_path).chain(additional_src_paths) {
+        if is_point_cloud_path(path) {
+            point_cloud.append(&load_point_cloud(path)?);
+        } else if is_heightmap_path(path) {
+            mesh.append(&load_heightmap(path, heightmap_height)?);
+        } else {
+            mesh.append(&load_mesh(path)?);
+        }
+    }
+
+    if let Some(mesh_dst_path) = matches.value_of("MESH OUTPUT") {
+        let file = std::fs::File::create(mesh_dst_path)?;
+        mesh.write_binary(&mut BufWriter::new(file))?;
+    }
+
+    // The deviation is measured before the mesh is centered, while it is still
+    // aligned with the reference.
+    if let Some(reference_path) = matches.value_of("DEVIATION REFERENCE") {
+        apply_deviation_colors(&mut mesh, &load_mesh(reference_path)?);
+    }
+    if matches.is_present("HIGHLIGHT DEFECTS") {
+        apply_defect_colors(&mut mesh);
+    }
 
-    let (bounds_min, bounds_max) = mesh.bounding_box().ok_or(Error::EmptyMesh)?;
-    let center = bounds_min + (bounds_max - bounds_min) / 2.0;
-    let center_to_origin = Point3::new(0.0f32, 0.0f32, 0.0f32) - center;
-    mesh.transform(Matrix4::from_translation(center_to_origin));
+    if mesh.positions.is_empty() && point_cloud.positions.is_empty() {
+        return Err(Error::EmptyMesh);
+    }
+    center_at_origin(&mut mesh, &mut point_cloud);
 
-    let bounding_sphere_radius = max_distance_from_origin(&mesh);
+    let bounding_sphere_radius =
+        max_distance_from_origin(mesh.positions.iter().chain(&point_cloud.positions));
+    if let Some(sample_count) = ambient_occlusion_samples {
+        apply_ambient_occlusion(&mut mesh, sample_count, bounding_sphere_radius / 2.0);
+    }
     let camera_dist = bounding_sphere_radius / f32::sin(Rad::from(camera_fovy / 2.0).0);
     let camera_position = (Matrix4::from_angle_z(camera_phi) * Matrix4::from_angle_y(camera_theta))
         .transform_point(Point3::new(0.0, 0.0, camera_dist));
@@ -125,14 +213,25 @@ fn main() -> Result<()> {
         * Matrix4::from_angle_y(light_theta))
     .transform_point(Point3::new(0.0, 0.0, camera_dist));
 
+    let mut scene = Scene::new();
+    if !mesh.positions.is_empty() {
+        scene.add(SceneNode::with_mesh(&mesh, Matrix4::identity()));
+    }
+    if !point_cloud.positions.is_empty() {
+        scene.add(SceneNode::with_point_cloud(
+            &point_cloud,
+            Matrix4::identity(),
+        ));
+    }
     let config = wgpu_renderer::Config {
-        mesh: &mesh,
+        scene: &scene,
         width,
         height,
         camera_fovy,
         camera_position,
         point_light_position,
         point_light_intensity,
+        point_size,
     };
     let pixels = futures::executor::block_on(wgpu_renderer::render(config))?;
     let mut image: ImageBuffer<Rgba<u8>, _> =
@@ -156,10 +255,140 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-/// Return the maximum distance between any vertex and the origin.
-fn max_distance_from_origin(mesh: &Mesh) -> f32 {
-    mesh.positions
-        .iter()
+/// Load a mesh from the file at the given path. Files with the `.rmsh`
+/// extension are read in the native binary format, and all other files are read
+/// as STL.
+fn load_mesh(path: &str) -> Result<Mesh> {
+    let file = std::fs::File::open(path)?;
+    let mut reader = BufReader::new(&file);
+    if Path::new(path).extension().is_some_and(|e| e == "rmsh") {
+        Ok(Mesh::read_binary(&mut reader)?)
+    } else {
+        Ok(MeshBuilder::from_stl(&mut reader)?.build())
+    }
+}
+
+/// Return whether the file at the given path should be loaded as a point cloud,
+/// based on its extension.
+fn is_point_cloud_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ["ply", "xyz"].contains(&e.to_lowercase().as_str()))
+}
+
+/// Load a point cloud from the file at the given path. Files with the `.ply`
+/// extension are read as PLY, and all other files are read as XYZ.
+fn load_point_cloud(path: &str) -> Result<PointCloud> {
+    let file = std::fs::File::open(path)?;
+    let mut reader = BufReader::new(&file);
+    let is_ply = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.to_lowercase() == "ply");
+    if is_ply {
+        Ok(PointCloud::from_ply(&mut reader)?)
+    } else {
+        Ok(PointCloud::from_xyz(&mut reader)?)
+    }
+}
+
+/// Return whether the file at the given path should be loaded as a heightmap,
+/// based on its extension.
+fn is_heightmap_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| {
+            ["png", "jpg", "jpeg", "bmp", "tif", "tiff"].contains(&e.to_lowercase().as_str())
+        })
+}
+
+/// Load a grayscale image and build a closed mesh from it, with each pixel
+/// one unit wide. White pixels are `height` units tall, or a tenth of the
+/// image's larger dimension if no height is given.
+fn load_heightmap(path: &str, height: Option<f32>) -> Result<Mesh> {
+    let image = image::open(path)?.to_luma16();
+    let (width, rows) = (image.width() as usize, image.height() as usize);
+    let heightmap = Heightmap::from_luma16(width, rows, image.as_raw())?;
+    let scale_z = height.unwrap_or(width.max(rows) as f32 / 10.0);
+    Ok(MeshBuilder::from_heightmap(&heightmap, 1.0, scale_z, true).build())
+}
+
+/// Move the mesh and the point cloud together so that the center of the
+/// bounding box around both of them is at the origin.
+fn center_at_origin(mesh: &mut Mesh, point_cloud: &mut PointCloud) {
+    let bounds = mesh
+        .bounding_box()
+        .into_iter()
+        .chain(point_cloud.bounding_box())
+        .reduce(|(min1, max1), (min2, max2)| {
+            (
+                point3(min1.x.min(min2.x), min1.y.min(min2.y), min1.z.min(min2.z)),
+                point3(max1.x.max(max2.x), max1.y.max(max2.y), max1.z.max(max2.z)),
+            )
+        });
+    if let Some((min, max)) = bounds {
+        let offset = point3(0.0, 0.0, 0.0) - (min + (max - min) / 2.0);
+        mesh.translate(offset);
+        point_cloud.translate(offset);
+    }
+}
+
+/// Bake ambient occlusion into the mesh and multiply it into the mesh's vertex
+/// colors.
+fn apply_ambient_occlusion(mesh: &mut Mesh, sample_count: usize, max_distance: f32) {
+    let settings = AmbientOcclusionSettings {
+        sample_count,
+        max_distance,
+        ..AmbientOcclusionSettings::default()
+    };
+    let occlusion = mesh.ambient_occlusion(&settings);
+    let colors = mesh
+        .colors
+        .take()
+        .unwrap_or_else(|| vec![Vector4::new(1.0, 1.0, 1.0, 1.0); mesh.positions.len()]);
+    mesh.colors = Some(
+        colors
+            .iter()
+            .zip(occlusion)
+            .map(|(c, ao)| (c.truncate() * ao).extend(c.w))
+            .collect(),
+    );
+}
+
+/// Color the mesh by the signed distance from each vertex to the reference
+/// mesh's surface, with a color range that is symmetric about zero.
+fn apply_deviation_colors(mesh: &mut Mesh, reference: &Mesh) {
+    if let Some(deviations) = mesh.vertex_deviations(reference) {
+        // A perfect match still maps zero to the middle of the color range.
+        let max_deviation = deviations
+            .iter()
+            .fold(f32::MIN_POSITIVE, |acc, d| acc.max(d.abs()));
+        mesh.set_colors_from_values(&deviations, -max_deviation, max_deviation);
+    }
+}
+
+/// Color the vertices of the mesh's defective triangles red, keeping the
+/// colors of the other vertices.
+fn apply_defect_colors(mesh: &mut Mesh) {
+    let report = mesh.find_defects(&DefectSettings::default());
+    let mut colors = mesh
+        .colors
+        .take()
+        .unwrap_or_else(|| vec![Vector4::new(1.0, 1.0, 1.0, 1.0); mesh.positions.len()]);
+    for t in report.defective_triangles() {
+        let (i1, i2, i3) = mesh.triangle_vertex_indices[t];
+        for i in [i1, i2, i3] {
+            colors[i] = Vector4::new(1.0, 0.0, 0.0, 1.0);
+        }
+    }
+    mesh.colors = Some(colors);
+}
+
+/// Return the maximum distance between any of the positions and the origin.
+fn max_distance_from_origin<'a>(positions: impl Iterator<Item = &'a Point3<f32>>) -> f32 {
+    positions
         .fold(0.0f32, |acc, p| {
             let dist2 = (p - point3(0.0, 0.0, 0.0)).magnitude2();
             acc.max(dist2)